@@ -2,8 +2,28 @@ from adblock.adblock import (
     __version__,
     Engine,
     FilterSet,
+    FilterSetStats,
+    FilterFormat,
+    RuleTypes,
     BlockerResult,
+    BlockerAction,
     UrlSpecificResources,
+    Request,
+    RequestType,
+    Explanation,
+    ExplainedFilter,
+    FilterMatches,
+    ParseReport,
+    RejectedFilter,
+    FilterListMetadata,
+    LintFinding,
+    NetworkFilter,
+    CosmeticFilter,
+    ContentBlockingRules,
+    DroppedFilter,
+    DeclarativeNetRequestRules,
+    parse_filter,
+    normalize_filter,
     AdblockException,
     BlockerException,
     SerializationError,
@@ -14,14 +34,42 @@ from adblock.adblock import (
     AddResourceException,
     InvalidUtf8ContentError,
     InvalidBase64ContentError,
+    InvalidRequestError,
+    InvalidRequestTypeError,
+    InvalidOptionError,
+    FilterParseError,
+    ConversionError,
+    InvalidUrlError,
+    InvalidRequestUrlError,
+    InvalidSourceUrlError,
 )
 
 
 __all__ = (
     "Engine",
     "FilterSet",
+    "FilterSetStats",
+    "FilterFormat",
+    "RuleTypes",
     "BlockerResult",
+    "BlockerAction",
     "UrlSpecificResources",
+    "Request",
+    "RequestType",
+    "Explanation",
+    "ExplainedFilter",
+    "FilterMatches",
+    "ParseReport",
+    "RejectedFilter",
+    "FilterListMetadata",
+    "LintFinding",
+    "NetworkFilter",
+    "CosmeticFilter",
+    "ContentBlockingRules",
+    "DroppedFilter",
+    "DeclarativeNetRequestRules",
+    "parse_filter",
+    "normalize_filter",
     "AdblockException",
     "BlockerException",
     "SerializationError",
@@ -32,4 +80,12 @@ __all__ = (
     "AddResourceException",
     "InvalidUtf8ContentError",
     "InvalidBase64ContentError",
+    "InvalidRequestError",
+    "InvalidRequestTypeError",
+    "InvalidOptionError",
+    "FilterParseError",
+    "ConversionError",
+    "InvalidUrlError",
+    "InvalidRequestUrlError",
+    "InvalidSourceUrlError",
 )
@@ -1,4 +1,6 @@
-from typing import Optional, Dict, List, Set
+from datetime import timedelta
+from os import PathLike
+from typing import Callable, Iterable, Optional, Dict, Iterator, List, Sequence, Set, Tuple, Union
 
 __version__: str
 
@@ -32,15 +34,77 @@ class InvalidUtf8ContentError(AddResourceException):
 class InvalidBase64ContentError(AddResourceException):
     pass
 
+class InvalidRequestError(AdblockException):
+    pass
+
+class InvalidRequestTypeError(AdblockException):
+    pass
+
+class InvalidOptionError(AdblockException):
+    pass
+
+class FilterParseError(AdblockException):
+    text: str
+    filter_type: Optional[str]
+    kind: Optional[str]
+
+class ConversionError(AdblockException):
+    pass
+
+class InvalidUrlError(InvalidRequestError):
+    url: str
+    reason: str
+
+class InvalidRequestUrlError(InvalidUrlError):
+    pass
+
+class InvalidSourceUrlError(InvalidUrlError):
+    pass
+
+class BlockerAction:
+    ALLOW: BlockerAction
+    BLOCK: BlockerAction
+    REDIRECT: BlockerAction
+    EXCEPTED: BlockerAction
+    value: str
+
 class BlockerResult:
     matched: bool
     explicit_cancel: bool
     important: bool
     redirect_type: Optional[str]
+    redirect_resource_name: Optional[str]
+    redirect_mime_type: Optional[str]
+    redirect_bytes: Optional[bytes]
+    redirect_data_url: Optional[str]
+    action: BlockerAction
     redirect: Optional[str]
     exception: Optional[str]
     filter: Optional[str]
     error: Optional[str]
+    filter_source: Optional[str]
+    exception_source: Optional[str]
+    __match_args__: Tuple[str, ...]
+    def __init__(
+        self,
+        matched: bool = False,
+        important: bool = False,
+        redirect: Optional[str] = None,
+        redirect_type: Optional[str] = None,
+        redirect_resource_name: Optional[str] = None,
+        exception: Optional[str] = None,
+        filter: Optional[str] = None,
+        error: Optional[str] = None,
+        filter_source: Optional[str] = None,
+        exception_source: Optional[str] = None,
+    ) -> None:
+        pass
+    def to_dict(self) -> Dict[str, Union[bool, Optional[str]]]:
+        pass
+    def __eq__(self, other: object) -> bool:
+        pass
+    def __hash__(self) -> int:
+        pass
     def __repr__(self) -> str:
         pass
 
@@ -49,6 +113,203 @@ class UrlSpecificResources:
     style_selectors: Dict[str, List[str]]
     exceptions: Set[str]
     injected_script: str
+    selector_sources: Dict[str, List[str]]
+    def __repr__(self) -> str:
+        pass
+
+class RequestType:
+    BEACON: RequestType
+    CSP_REPORT: RequestType
+    DOCUMENT: RequestType
+    FONT: RequestType
+    IMAGE: RequestType
+    MEDIA: RequestType
+    OBJECT: RequestType
+    OTHER: RequestType
+    PING: RequestType
+    SCRIPT: RequestType
+    SPECULATIVE: RequestType
+    STYLESHEET: RequestType
+    SUBDOCUMENT: RequestType
+    WEB_MANIFEST: RequestType
+    WEBSOCKET: RequestType
+    XBL: RequestType
+    XML_DTD: RequestType
+    XMLHTTPREQUEST: RequestType
+    XSLT: RequestType
+    value: str
+
+class FilterFormat:
+    STANDARD: FilterFormat
+    HOSTS: FilterFormat
+    DOMAINS: FilterFormat
+    DNSMASQ: FilterFormat
+    ADGUARD_DNS: FilterFormat
+    AUTO: FilterFormat
+    value: str
+
+class RuleTypes:
+    ALL: RuleTypes
+    NETWORK_ONLY: RuleTypes
+    COSMETIC_ONLY: RuleTypes
+    value: str
+
+class Request:
+    url: str
+    hostname: str
+    domain: str
+    source_hostname: str
+    source_domain: str
+    request_type: str
+    third_party: Optional[bool]
+    def __init__(
+        self,
+        url: str,
+        source_url: str,
+        request_type: Union[RequestType, str],
+        strict: bool = False,
+    ) -> None:
+        pass
+    def __repr__(self) -> str:
+        pass
+
+class ExplainedFilter:
+    kind: str
+    filter: Optional[str]
+    tag: Optional[str]
+    cancelled_by: Optional[str]
+    def __repr__(self) -> str:
+        pass
+
+class Explanation:
+    result: BlockerResult
+    matches: List[ExplainedFilter]
+    def __repr__(self) -> str:
+        pass
+
+class FilterMatches:
+    block: List[ExplainedFilter]
+    exception: List[ExplainedFilter]
+    important: List[ExplainedFilter]
+    redirect: List[ExplainedFilter]
+    csp: List[ExplainedFilter]
+    def __repr__(self) -> str:
+        pass
+
+class RejectedFilter:
+    line_number: int
+    include: Optional[str]
+    text: str
+    filter_type: str
+    kind: str
+    def __repr__(self) -> str:
+        pass
+
+class FilterListMetadata:
+    title: Optional[str]
+    homepage: Optional[str]
+    version: Optional[str]
+    last_modified: Optional[str]
+    redirect: Optional[str]
+    expires: Optional[timedelta]
+    def __repr__(self) -> str:
+        pass
+
+class ParseReport:
+    network_filters: int
+    cosmetic_filters: int
+    ignored: int
+    rejected: List[RejectedFilter]
+    unresolved_includes: List[str]
+    include_cycles: List[str]
+    metadata: FilterListMetadata
+    def __repr__(self) -> str:
+        pass
+
+class FilterSetStats:
+    network_filters: int
+    exception_filters: int
+    redirect_filters: int
+    cosmetic_filters: int
+    scriptlet_filters: int
+    def __repr__(self) -> str:
+        pass
+
+class NetworkFilter:
+    text: str
+    exception: bool
+    pattern: str
+    is_regex: bool
+    left_anchor: bool
+    hostname_anchor: bool
+    right_anchor: bool
+    domains: Set[str]
+    excluded_domains: Set[str]
+    request_types: Set[str]
+    first_party: bool
+    third_party: bool
+    important: bool
+    match_case: bool
+    badfilter: bool
+    generichide: bool
+    redirect: Optional[str]
+    tag: Optional[str]
+    csp: Optional[str]
+    def __repr__(self) -> str:
+        pass
+
+class CosmeticFilter:
+    text: str
+    exception: bool
+    hostnames: Set[str]
+    excluded_hostnames: Set[str]
+    selector: Optional[str]
+    style: Optional[str]
+    scriptlet: Optional[str]
+    scriptlet_args: List[str]
+    def __repr__(self) -> str:
+        pass
+
+def parse_filter(
+    text: str, include_redirect_urls: bool = False
+) -> Union[NetworkFilter, CosmeticFilter]:
+    pass
+
+def normalize_filter(text: str, include_redirect_urls: bool = False) -> str:
+    pass
+
+class LintFinding:
+    kind: str
+    message: str
+    text: Optional[str]
+    line_number: int
+    include: Optional[str]
+    source: Optional[str]
+    def __repr__(self) -> str:
+        pass
+
+class DroppedFilter:
+    text: str
+    filter_type: str
+    kind: str
+    line_number: int
+    include: Optional[str]
+    source: Optional[str]
+    def __repr__(self) -> str:
+        pass
+
+class ContentBlockingRules:
+    json: str
+    rule_count: int
+    dropped: List[DroppedFilter]
+    def __repr__(self) -> str:
+        pass
+
+class DeclarativeNetRequestRules:
+    json: str
+    rule_count: int
+    regex_rule_count: int
+    dropped: List[DroppedFilter]
     def __repr__(self) -> str:
         pass
 
@@ -58,34 +319,120 @@ class FilterSet:
     def add_filter_list(
         self,
         filter_list: str,
-        format: str = "standard",
+        format: Union[FilterFormat, str] = FilterFormat.STANDARD,
         include_redirect_urls: bool = False,
-        rule_types: str = "all",
-    ) -> None:
-        pass
+        rule_types: Union[RuleTypes, str] = RuleTypes.ALL,
+        env: Optional[Iterable[str]] = None,
+        include_resolver: Optional[
+            Union[Callable[[str], Optional[str]], str, PathLike[str]]
+        ] = None,
+        source: Optional[str] = None,
+        canonicalize: bool = False,
+    ) -> ParseReport:
+        """`source` identifies the list the filters came from. The sources of
+        network filters are only reported by the `Engine` if this FilterSet
+        was created with `debug=True`."""
+    def add_filter_list_from_file(
+        self,
+        path: Union[str, PathLike[str]],
+        format: Union[FilterFormat, str] = FilterFormat.STANDARD,
+        include_redirect_urls: bool = False,
+        rule_types: Union[RuleTypes, str] = RuleTypes.ALL,
+        source: Optional[str] = None,
+        canonicalize: bool = False,
+    ) -> ParseReport:
+        """`source` identifies the list the filters came from. The sources of
+        network filters are only reported by the `Engine` if this FilterSet
+        was created with `debug=True`."""
     def add_filters(
         self,
         filters: List[str],
-        format: str = "standard",
+        format: Union[FilterFormat, str] = FilterFormat.STANDARD,
         include_redirect_urls: bool = False,
-        rule_types: str = "all",
-    ) -> None:
+        rule_types: Union[RuleTypes, str] = RuleTypes.ALL,
+        source: Optional[str] = None,
+        canonicalize: bool = False,
+    ) -> ParseReport:
+        """`source` identifies the list the filters came from. The sources of
+        network filters are only reported by the `Engine` if this FilterSet
+        was created with `debug=True`."""
+    def merge(self, other: FilterSet) -> None:
+        pass
+    def copy(self) -> FilterSet:
+        pass
+    def __or__(self, other: FilterSet) -> FilterSet:
+        pass
+    def __ior__(self, other: FilterSet) -> FilterSet:
+        pass
+    def __copy__(self) -> FilterSet:
+        pass
+    def serialize(self) -> bytes:
+        pass
+    def deserialize(self, serialized: bytes) -> None:
+        pass
+    def __getstate__(self) -> bytes:
+        pass
+    def __setstate__(self, state: bytes) -> None:
+        pass
+    def lint(self) -> List[LintFinding]:
+        pass
+    def to_content_blocking(self) -> ContentBlockingRules:
+        pass
+    def to_declarative_net_request(
+        self,
+        max_rules: Optional[int] = 30000,
+        max_regex_rules: Optional[int] = 1000,
+        resource_path: str = "/",
+    ) -> DeclarativeNetRequestRules:
+        pass
+    def stats(self) -> FilterSetStats:
+        pass
+    def __len__(self) -> int:
+        pass
+    def __iter__(self) -> Iterator[str]:
         pass
 
 class Engine:
-    def __init__(self, filter_set: FilterSet, optimize: bool = True) -> None:
+    def __init__(
+        self, filter_set: FilterSet, optimize: bool = True, keep_filters: bool = False
+    ) -> None:
         pass
     def check_network_urls(
-        self, url: str, source_url: str, request_type: str
+        self,
+        url: str,
+        source_url: str,
+        request_type: Union[RequestType, str],
+        strict: bool = False,
     ) -> BlockerResult:
         pass
+    def check(self, request: Request) -> BlockerResult:
+        pass
+    def explain(
+        self, url: str, source_url: str, request_type: Union[RequestType, str]
+    ) -> Explanation:
+        pass
+    def check_network_urls_all_matches(
+        self,
+        url: str,
+        source_url: str,
+        request_type: Union[RequestType, str],
+        strict: bool = False,
+    ) -> FilterMatches:
+        pass
+    def check_network_urls_batch(
+        self,
+        requests: Sequence[Tuple[str, str, Union[RequestType, str]]],
+        strict: bool = False,
+    ) -> List[BlockerResult]:
+        pass
     def check_network_urls_with_hostnames(
         self,
         url: str,
         hostname: str,
         source_hostname: str,
-        request_type: str,
+        request_type: Union[RequestType, str],
         third_party_request: Optional[bool],
+        strict: bool = False,
     ) -> BlockerResult:
         pass
     def check_network_urls_with_hostnames_subset(
@@ -93,10 +440,11 @@ class Engine:
         url: str,
         hostname: str,
         source_hostname: str,
-        request_type: str,
+        request_type: Union[RequestType, str],
         third_party_request: Optional[bool],
         previously_matched_rule: bool,
         force_check_exceptions: bool,
+        strict: bool = False,
     ) -> BlockerResult:
         pass
     def serialize(self) -> bytes:
@@ -0,0 +1,352 @@
+//! Tracing of the network filters that the engine considers when checking a
+//! request, used to explain how a decision was reached.
+
+use adblock::filters::network::{NetworkFilter, NetworkMatchable};
+use adblock::request::{Request as RustRequest, RequestType as RustRequestType};
+use pyo3::prelude::*;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{BlockerResult, DiyPythonRepr};
+
+/// How a network filter that matched a request was considered by the engine.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum MatchKind {
+    /// The filter was cancelled by a `$badfilter` rule when the engine was
+    /// created.
+    BadFilter,
+    /// An `$important` filter, which blocks regardless of exceptions.
+    Important,
+    /// A blocking filter.
+    Block,
+    /// A `$tag` gated filter, skipped because its tag is not enabled.
+    TagDisabled,
+    /// An exception filter.
+    Exception,
+    /// A filter with a `$redirect`, `$redirect-rule` or `$redirect-url`
+    /// option.
+    Redirect,
+    /// An exception filter for redirects, which cancels all redirects.
+    RedirectException,
+    /// A filter with a `$csp` option.
+    Csp,
+}
+
+impl MatchKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::BadFilter => "badfilter",
+            Self::Important => "important",
+            Self::Block => "block",
+            Self::TagDisabled => "tag_disabled",
+            Self::Exception => "exception",
+            Self::Redirect => "redirect",
+            Self::RedirectException => "redirect_exception",
+            Self::Csp => "csp",
+        }
+    }
+}
+
+/// A network filter that matched a request, along with how it was used.
+pub(crate) struct Match<'a> {
+    pub kind: MatchKind,
+    pub filter: &'a NetworkFilter,
+    /// The `$badfilter` rule cancelling `filter`, for `MatchKind::BadFilter`.
+    pub cancelled_by: Option<&'a NetworkFilter>,
+}
+
+/// The network filters of an `Engine` created with `keep_filters=True`,
+/// sorted into the lists the blocker uses when it is created. Mirrors
+/// `Blocker::new` in the upstream library.
+#[derive(Default)]
+pub(crate) struct FilterLists {
+    /// The filters, which the lists refer to by their index.
+    filters: Vec<NetworkFilter>,
+    /// Filters cancelled by a `$badfilter` rule, along with that rule.
+    cancelled: Vec<(usize, usize)>,
+    importants: Vec<usize>,
+    tagged: Vec<usize>,
+    blocking: Vec<usize>,
+    exceptions: Vec<usize>,
+    redirects: Vec<usize>,
+    csp: Vec<usize>,
+}
+
+impl FilterLists {
+    pub(crate) fn new(filters: Vec<NetworkFilter>) -> Self {
+        let badfilters: HashMap<_, _> = filters
+            .iter()
+            .enumerate()
+            .filter(|(_, filter)| filter.is_badfilter())
+            .map(|(index, filter)| (filter.get_id_without_badfilter(), index))
+            .collect();
+
+        let mut lists = Self::default();
+        for (index, filter) in filters.iter().enumerate() {
+            if filter.is_badfilter() {
+                continue;
+            }
+            if let Some(&badfilter) = badfilters.get(&filter.get_id()) {
+                lists.cancelled.push((index, badfilter));
+                continue;
+            }
+
+            if filter.is_redirect() {
+                lists.redirects.push(index);
+            }
+
+            if filter.is_csp() {
+                lists.csp.push(index);
+            } else if filter.is_generic_hide() {
+                // Not used when checking network requests.
+            } else if filter.is_exception() {
+                lists.exceptions.push(index);
+            } else if filter.is_important() {
+                lists.importants.push(index);
+            } else if filter.tag.is_some() && !filter.is_redirect() {
+                lists.tagged.push(index);
+            } else if !filter.is_redirect() || filter.also_block_redirect() {
+                lists.blocking.push(index);
+            }
+        }
+        lists.filters = filters;
+        lists
+    }
+
+    /// Returns every network filter matching `request`, in the order in which
+    /// the blocker evaluates them: `$badfilter` cancellations, `$important`
+    /// filters, tagged and regular blocking filters, exceptions and finally
+    /// redirects.
+    pub(crate) fn trace(
+        &self,
+        request: &RustRequest,
+        tags_enabled: &HashSet<String>,
+    ) -> Vec<Match<'_>> {
+        let mut matches = Vec::new();
+        if !request.is_supported {
+            return matches;
+        }
+
+        let no_tags = HashSet::new();
+        for &(index, badfilter) in &self.cancelled {
+            let filter = &self.filters[index];
+            if filter.matches(request) {
+                matches.push(Match {
+                    kind: MatchKind::BadFilter,
+                    filter,
+                    cancelled_by: Some(&self.filters[badfilter]),
+                });
+            }
+        }
+
+        let mut push_matching =
+            |list: &[usize],
+             active_tags: &HashSet<String>,
+             kind_of: &dyn Fn(&NetworkFilter) -> MatchKind| {
+                for &index in list {
+                    let filter = &self.filters[index];
+                    if !filter.matches(request) {
+                        continue;
+                    }
+                    let tag_enabled = filter
+                        .tag
+                        .as_ref()
+                        .map(|tag| active_tags.contains(tag))
+                        .unwrap_or(true);
+                    matches.push(Match {
+                        kind: if tag_enabled {
+                            kind_of(filter)
+                        } else {
+                            MatchKind::TagDisabled
+                        },
+                        filter,
+                        cancelled_by: None,
+                    });
+                }
+            };
+
+        push_matching(&self.importants, &no_tags, &|_| MatchKind::Important);
+        push_matching(&self.tagged, tags_enabled, &|_| MatchKind::Block);
+        push_matching(&self.blocking, &no_tags, &|_| MatchKind::Block);
+        push_matching(&self.exceptions, tags_enabled, &|_| MatchKind::Exception);
+        push_matching(&self.redirects, &no_tags, &|filter| {
+            if filter.is_exception() {
+                MatchKind::RedirectException
+            } else {
+                MatchKind::Redirect
+            }
+        });
+
+        matches
+    }
+
+    /// Returns every network filter applying to `request`, grouped by the
+    /// role it plays. Unlike `trace`, filters cancelled by `$badfilter` or
+    /// gated by a disabled `$tag` are left out, while `$csp` filters are
+    /// included.
+    pub(crate) fn collect_all(
+        &self,
+        request: &RustRequest,
+        tags_enabled: &HashSet<String>,
+    ) -> FilterMatches {
+        let mut all = FilterMatches::default();
+        for m in self.trace(request, tags_enabled) {
+            let group = match m.kind {
+                MatchKind::Important => &mut all.important,
+                MatchKind::Block => &mut all.block,
+                MatchKind::Exception => &mut all.exception,
+                MatchKind::Redirect => &mut all.redirect,
+                // Redirect exceptions are already reported as exceptions.
+                MatchKind::BadFilter | MatchKind::TagDisabled | MatchKind::RedirectException => {
+                    continue
+                }
+                MatchKind::Csp => &mut all.csp,
+            };
+            group.push(ExplainedFilter::from(&m));
+        }
+
+        // Like `Blocker::get_csp_directives`, `$csp` filters only apply to
+        // documents.
+        if !request.is_supported
+            || !matches!(
+                request.request_type,
+                RustRequestType::Document | RustRequestType::Subdocument
+            )
+        {
+            return all;
+        }
+        for &index in &self.csp {
+            let filter = &self.filters[index];
+            let tag_enabled = filter
+                .tag
+                .as_ref()
+                .map(|tag| tags_enabled.contains(tag))
+                .unwrap_or(true);
+            if !tag_enabled || !filter.matches(request) {
+                continue;
+            }
+            let (group, kind) = if filter.is_exception() {
+                (&mut all.exception, MatchKind::Exception)
+            } else {
+                (&mut all.csp, MatchKind::Csp)
+            };
+            group.push(ExplainedFilter::from(&Match {
+                kind,
+                filter,
+                cancelled_by: None,
+            }));
+        }
+        all
+    }
+}
+
+fn raw_text(filter: &NetworkFilter) -> Option<String> {
+    filter.raw_line.as_ref().map(|line| line.to_string())
+}
+
+/// A network filter that matched a request, as reported by `Engine.explain`
+/// and `Engine.check_network_urls_all_matches`.
+#[pyclass]
+#[derive(Clone)]
+pub struct ExplainedFilter {
+    /// How the filter was used by the engine. One of `"badfilter"`,
+    /// `"important"`, `"block"`, `"tag_disabled"`, `"exception"`,
+    /// `"redirect"`, `"redirect_exception"` and `"csp"`.
+    #[pyo3(get)]
+    pub kind: String,
+    /// The text of the filter, if the `FilterSet` the engine was created
+    /// from had debugging enabled. Otherwise `None`.
+    #[pyo3(get)]
+    pub filter: Option<String>,
+    /// The value of the filter's `$tag` option, if any.
+    #[pyo3(get)]
+    pub tag: Option<String>,
+    /// The text of the `$badfilter` rule that cancelled the filter, if its
+    /// kind is `"badfilter"` and debugging was enabled.
+    #[pyo3(get)]
+    pub cancelled_by: Option<String>,
+}
+
+impl From<&Match<'_>> for ExplainedFilter {
+    fn from(m: &Match<'_>) -> Self {
+        Self {
+            kind: m.kind.as_str().to_string(),
+            filter: raw_text(m.filter),
+            tag: m.filter.tag.clone(),
+            cancelled_by: m.cancelled_by.and_then(raw_text),
+        }
+    }
+}
+
+#[pymethods]
+impl ExplainedFilter {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "ExplainedFilter(kind={}, filter={}, tag={}, cancelled_by={})",
+            self.kind.diy_python_repr(),
+            self.filter.diy_python_repr(),
+            self.tag.diy_python_repr(),
+            self.cancelled_by.diy_python_repr(),
+        ))
+    }
+}
+
+/// A report of how the engine reached its decision for a network request,
+/// returned by `Engine.explain`.
+#[pyclass]
+pub struct Explanation {
+    /// The decision, as returned by `Engine.check_network_urls`.
+    #[pyo3(get)]
+    pub result: BlockerResult,
+    /// Every filter matching the request, in the order in which the engine
+    /// evaluates them.
+    #[pyo3(get)]
+    pub matches: Vec<ExplainedFilter>,
+}
+
+#[pymethods]
+impl Explanation {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "Explanation<matched={}, {} matching filters>",
+            self.result.matched.diy_python_repr(),
+            self.matches.len(),
+        ))
+    }
+}
+
+/// Every network filter applying to a request, grouped by role. Returned by
+/// `Engine.check_network_urls_all_matches`.
+#[pyclass]
+#[derive(Default)]
+pub struct FilterMatches {
+    /// Blocking filters, including `$tag` gated filters whose tag is enabled.
+    #[pyo3(get)]
+    pub block: Vec<ExplainedFilter>,
+    /// Exception filters, including exceptions for redirects and `$csp`.
+    #[pyo3(get)]
+    pub exception: Vec<ExplainedFilter>,
+    /// `$important` filters, which are not subject to exceptions.
+    #[pyo3(get)]
+    pub important: Vec<ExplainedFilter>,
+    /// Filters with a `$redirect`, `$redirect-rule` or `$redirect-url` option.
+    #[pyo3(get)]
+    pub redirect: Vec<ExplainedFilter>,
+    /// Filters with a `$csp` option.
+    #[pyo3(get)]
+    pub csp: Vec<ExplainedFilter>,
+}
+
+#[pymethods]
+impl FilterMatches {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "FilterMatches<block={}, exception={}, important={}, redirect={}, csp={}>",
+            self.block.len(),
+            self.exception.len(),
+            self.important.len(),
+            self.redirect.len(),
+            self.csp.len(),
+        ))
+    }
+}
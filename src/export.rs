@@ -0,0 +1,563 @@
+//! Conversion of the filters in a `FilterSet` into the rule formats of
+//! browser content blocking APIs.
+
+use adblock::content_blocking::{
+    ignore_previous_fp_documents, CbRule, CbRuleCreationFailure, CbRuleEquivalent, CbType,
+};
+use adblock::filters::cosmetic::CosmeticFilter;
+use adblock::filters::network::NetworkFilter;
+use adblock::utils::Hash;
+use pyo3::prelude::*;
+use serde_json::{json, Map, Value};
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use crate::{cosmetic_text, filter, ConversionError, DiyPythonRepr, FilterOrigin};
+
+/// The `declarativeNetRequest` resource types, by the request type they
+/// correspond to.
+const DNR_RESOURCE_TYPES: &[(&str, &str)] = &[
+    ("document", "main_frame"),
+    ("subdocument", "sub_frame"),
+    ("stylesheet", "stylesheet"),
+    ("script", "script"),
+    ("image", "image"),
+    ("font", "font"),
+    ("object", "object"),
+    ("xmlhttprequest", "xmlhttprequest"),
+    ("ping", "ping"),
+    ("media", "media"),
+    ("websocket", "websocket"),
+    ("other", "other"),
+];
+
+// Rule priorities reproducing the precedence of the engine: exceptions
+// override blocking filters, redirects apply to requests that would be
+// blocked, and `$important` filters override exceptions. Between rules of the
+// same priority, Chrome prefers `allow` to `block`, and `block` to
+// `upgradeScheme` and `redirect`.
+const DNR_BLOCK_PRIORITY: u32 = 1;
+const DNR_EXCEPTION_PRIORITY: u32 = 2;
+const DNR_REDIRECT_PRIORITY: u32 = 2;
+const DNR_IMPORTANT_PRIORITY: u32 = 3;
+const DNR_IMPORTANT_REDIRECT_PRIORITY: u32 = 4;
+
+/// A filter that was left out when converting a `FilterSet`, since it has no
+/// equivalent in the target format.
+#[pyclass]
+#[derive(Clone)]
+pub struct DroppedFilter {
+    /// The text of the filter.
+    #[pyo3(get)]
+    pub text: String,
+    /// Whether the filter is a `"network"` or a `"cosmetic"` filter.
+    #[pyo3(get)]
+    pub filter_type: &'static str,
+    /// Why the filter was left out, such as `"redirect_unsupported"` or
+    /// `"badfiltered"`.
+    #[pyo3(get)]
+    pub kind: &'static str,
+    /// The 1-based line number of the filter in the list it was added from.
+    #[pyo3(get)]
+    pub line_number: usize,
+    /// The name of the list the filter was included from with an
+    /// `!#include` directive, if any.
+    #[pyo3(get)]
+    pub include: Option<String>,
+    /// The `source` the filter was added with, if any.
+    #[pyo3(get)]
+    pub source: Option<String>,
+}
+
+impl DroppedFilter {
+    pub(crate) fn new(
+        text: &str,
+        filter_type: &'static str,
+        kind: &'static str,
+        origin: &FilterOrigin,
+    ) -> Self {
+        Self {
+            text: text.to_string(),
+            filter_type,
+            kind,
+            line_number: origin.line_number,
+            include: origin.include.clone(),
+            source: origin.source.clone(),
+        }
+    }
+}
+
+#[pymethods]
+impl DroppedFilter {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "DroppedFilter(text={}, filter_type={}, kind={}, line_number={}, source={})",
+            self.text.diy_python_repr(),
+            self.filter_type.diy_python_repr(),
+            self.kind.diy_python_repr(),
+            self.line_number,
+            self.source.diy_python_repr(),
+        ))
+    }
+}
+
+/// Safari/WebKit content blocker rules, returned by
+/// `FilterSet.to_content_blocking`.
+#[pyclass]
+#[derive(Clone)]
+pub struct ContentBlockingRules {
+    /// The rules, as a JSON array to be loaded by a content blocker.
+    #[pyo3(get)]
+    pub json: String,
+    /// The number of rules in `json`.
+    #[pyo3(get)]
+    pub rule_count: usize,
+    /// The filters that could not be converted, network filters first, each
+    /// in the order they were added.
+    #[pyo3(get)]
+    pub dropped: Vec<DroppedFilter>,
+}
+
+#[pymethods]
+impl ContentBlockingRules {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "ContentBlockingRules<rule_count={}, dropped={}>",
+            self.rule_count,
+            self.dropped.len(),
+        ))
+    }
+}
+
+/// Returns the text of a network filter, which export formats need to
+/// recover the `$domain` names only kept as hashes.
+pub(crate) fn filter_text(filter: &NetworkFilter) -> PyResult<&str> {
+    match &filter.raw_line {
+        Some(raw_line) => Ok(raw_line),
+        None => Err(ConversionError::new_err(
+            "Filters can only be converted from a FilterSet created with debug=True",
+        )),
+    }
+}
+
+/// Returns the IDs of the filters cancelled by the `$badfilter` rules among
+/// `filters`.
+pub(crate) fn badfiltered_ids(filters: &[NetworkFilter]) -> HashSet<Hash> {
+    filters
+        .iter()
+        .filter(|filter| filter.is_badfilter())
+        .map(|filter| filter.get_id_without_badfilter())
+        .collect()
+}
+
+fn content_blocking_failure_kind(failure: &CbRuleCreationFailure) -> &'static str {
+    match failure {
+        CbRuleCreationFailure::NeedsDebugMode => "needs_debug_mode",
+        CbRuleCreationFailure::UnlessAndIfDomainTogetherUnsupported => {
+            "if_and_unless_domain_unsupported"
+        }
+        CbRuleCreationFailure::NoSupportedNetworkOptions(_) => "request_types_unsupported",
+        CbRuleCreationFailure::NetworkRedirectUnsupported => "redirect_unsupported",
+        CbRuleCreationFailure::NetworkGenerichideUnsupported => "generichide_unsupported",
+        CbRuleCreationFailure::NetworkBadFilterUnsupported => "badfilter_unsupported",
+        CbRuleCreationFailure::NetworkCspUnsupported => "csp_unsupported",
+        CbRuleCreationFailure::FullRegexUnsupported => "regex_unsupported",
+        CbRuleCreationFailure::OptimizedRulesUnsupported => "optimized_unsupported",
+        CbRuleCreationFailure::CosmeticEntitiesUnsupported => "entities_unsupported",
+        CbRuleCreationFailure::CosmeticStyleRulesNotSupported => "style_unsupported",
+        CbRuleCreationFailure::ScriptletInjectionsNotSupported => "scriptlet_unsupported",
+    }
+}
+
+/// Converts the filters of a `FilterSet` into content blocker rules, like
+/// the upstream `FilterSet::into_content_blocking`, but keeping track of the
+/// filters that could not be converted.
+pub(crate) fn to_content_blocking(
+    network_filters: &[NetworkFilter],
+    network_origins: &[FilterOrigin],
+    cosmetic_filters: &[CosmeticFilter],
+    cosmetic_origins: &[FilterOrigin],
+) -> PyResult<ContentBlockingRules> {
+    // `ignore-previous-rules` rules only apply to the rules before them, so
+    // they go last.
+    let mut rules: Vec<CbRule> = Vec::new();
+    let mut ignore_previous_rules: Vec<CbRule> = Vec::new();
+    let mut add_rule = |rule: CbRule| match rule.action.typ {
+        CbType::IgnorePreviousRules => ignore_previous_rules.push(rule),
+        _ => rules.push(rule),
+    };
+    let mut dropped = Vec::new();
+    let mut converted_network = false;
+
+    let badfiltered = badfiltered_ids(network_filters);
+    for (filter, origin) in network_filters.iter().zip(network_origins) {
+        let text = filter_text(filter)?;
+        // `$badfilter` rules are applied by leaving out the filters they
+        // cancel.
+        if filter.is_badfilter() {
+            continue;
+        }
+        if badfiltered.contains(&filter.get_id()) {
+            dropped.push(DroppedFilter::new(text, "network", "badfiltered", origin));
+            continue;
+        }
+        match CbRuleEquivalent::try_from(filter.clone()) {
+            Ok(equivalent) => {
+                converted_network = true;
+                equivalent.into_iter().for_each(&mut add_rule);
+            }
+            Err(failure) => {
+                let kind = content_blocking_failure_kind(&failure);
+                dropped.push(DroppedFilter::new(text, "network", kind, origin));
+            }
+        }
+    }
+
+    for (filter, origin) in cosmetic_filters.iter().zip(cosmetic_origins) {
+        // Cosmetic filters always keep their text, which the conversion needs.
+        match CbRule::try_from(filter.clone()) {
+            Ok(rule) => add_rule(rule),
+            Err(failure) => {
+                let kind = content_blocking_failure_kind(&failure);
+                let text = cosmetic_text(filter);
+                dropped.push(DroppedFilter::new(text, "cosmetic", kind, origin));
+            }
+        }
+    }
+
+    rules.append(&mut ignore_previous_rules);
+    // Filters don't apply to top-level documents by default, which content
+    // blockers can only approximate.
+    if converted_network {
+        rules.push(ignore_previous_fp_documents());
+    }
+
+    let json = serde_json::to_string(&rules).expect("content blocking rules are valid JSON");
+    Ok(ContentBlockingRules {
+        json,
+        rule_count: rules.len(),
+        dropped,
+    })
+}
+
+/// Chromium `declarativeNetRequest` rules, returned by
+/// `FilterSet.to_declarative_net_request`.
+#[pyclass]
+#[derive(Clone)]
+pub struct DeclarativeNetRequestRules {
+    /// The rules, as a JSON array to be used as a static ruleset.
+    #[pyo3(get)]
+    pub json: String,
+    /// The number of rules in `json`.
+    #[pyo3(get)]
+    pub rule_count: usize,
+    /// The number of rules in `json` using a `regexFilter`.
+    #[pyo3(get)]
+    pub regex_rule_count: usize,
+    /// The filters that could not be converted or did not fit in the
+    /// limits, in the order they were added.
+    #[pyo3(get)]
+    pub dropped: Vec<DroppedFilter>,
+}
+
+#[pymethods]
+impl DeclarativeNetRequestRules {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "DeclarativeNetRequestRules<rule_count={}, regex_rule_count={}, dropped={}>",
+            self.rule_count,
+            self.regex_rule_count,
+            self.dropped.len(),
+        ))
+    }
+}
+
+/// Returns whether `regex` only uses syntax supported by RE2, which Chrome
+/// compiles `regexFilter`s with. RE2 has no lookaround assertions or
+/// backreferences.
+fn is_re2_compatible(regex: &str) -> bool {
+    if ["(?=", "(?!", "(?<=", "(?<!"]
+        .iter()
+        .any(|assertion| regex.contains(assertion))
+    {
+        return false;
+    }
+    let mut chars = regex.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some('1'..='9') = chars.next() {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Returns the names of `domains` for a rule condition, sorted.
+fn dnr_domains(domains: &HashSet<String>) -> Result<Vec<String>, &'static str> {
+    let mut names = Vec::with_capacity(domains.len());
+    for domain in domains {
+        if domain.ends_with(".*") {
+            return Err("entities_unsupported");
+        }
+        if !domain.is_ascii() {
+            return Err("non_ascii_unsupported");
+        }
+        names.push(domain.to_ascii_lowercase());
+    }
+    names.sort_unstable();
+    names.dedup();
+    Ok(names)
+}
+
+/// Adds `types` to `condition` as its `resourceTypes`, unless they are the
+/// types a rule applies to by default, every type but `main_frame`.
+fn add_resource_types(condition: &mut Map<String, Value>, types: &[&str]) {
+    if types.len() != DNR_RESOURCE_TYPES.len() - 1 || types.contains(&"main_frame") {
+        condition.insert("resourceTypes".into(), json!(types));
+    }
+}
+
+fn dnr_rule(priority: u32, action: Value, condition: Map<String, Value>) -> Value {
+    json!({
+        "priority": priority,
+        "action": action,
+        "condition": condition,
+    })
+}
+
+/// Converts a network filter, parsed from `text`, into the
+/// `declarativeNetRequest` rules with the same effect, without their IDs.
+/// Returns the kind of `DroppedFilter` if there are none.
+fn dnr_rules(
+    filter: &NetworkFilter,
+    text: &str,
+    resource_path: &str,
+) -> Result<Vec<Value>, &'static str> {
+    if filter.is_generic_hide() {
+        return Err("generichide_unsupported");
+    }
+    // Tagged filters only apply once their tag is enabled in the engine.
+    if filter.tag.is_some() {
+        return Err("tag_unsupported");
+    }
+    let view = filter::NetworkFilter::new(filter.clone(), text);
+
+    let mut condition = Map::new();
+    if view.is_regex {
+        // Upstream also treats `/` as a complete regex, and an empty regex
+        // would match every URL.
+        if view.pattern.len() <= 2 {
+            return Err("regex_unsupported");
+        }
+        let regex = &view.pattern[1..view.pattern.len() - 1];
+        if !is_re2_compatible(regex) {
+            return Err("regex_unsupported");
+        }
+        condition.insert("regexFilter".into(), regex.into());
+        condition.insert("isUrlFilterCaseSensitive".into(), view.match_case.into());
+    } else if !view.pattern.is_empty() && view.pattern != "*" {
+        if !view.pattern.is_ascii() {
+            return Err("non_ascii_unsupported");
+        }
+        // `urlFilter`s use the syntax of filters, except that `||` can't be
+        // followed by a wildcard.
+        if view.hostname_anchor && view.pattern.starts_with('*') {
+            return Err("pattern_unsupported");
+        }
+        let mut url_filter = String::new();
+        if view.hostname_anchor {
+            url_filter.push_str("||");
+        } else if view.left_anchor {
+            url_filter.push('|');
+        }
+        url_filter.push_str(&view.pattern);
+        if view.right_anchor {
+            url_filter.push('|');
+        }
+        condition.insert("urlFilter".into(), url_filter.into());
+        condition.insert("isUrlFilterCaseSensitive".into(), view.match_case.into());
+    }
+
+    let domains = dnr_domains(&view.domains)?;
+    if !domains.is_empty() {
+        condition.insert("initiatorDomains".into(), domains.into());
+    }
+    let excluded_domains = dnr_domains(&view.excluded_domains)?;
+    if !excluded_domains.is_empty() {
+        condition.insert("excludedInitiatorDomains".into(), excluded_domains.into());
+    }
+
+    match (view.first_party, view.third_party) {
+        (true, true) => {}
+        (true, false) => {
+            condition.insert("domainType".into(), "firstParty".into());
+        }
+        (false, true) => {
+            condition.insert("domainType".into(), "thirdParty".into());
+        }
+        (false, false) => return Err("never_matches"),
+    }
+
+    let types: Vec<&str> = DNR_RESOURCE_TYPES
+        .iter()
+        .filter(|(request_type, _)| view.request_types.contains(request_type))
+        .map(|(_, resource_type)| *resource_type)
+        .collect();
+    if types.is_empty() {
+        return Err("request_types_unsupported");
+    }
+
+    if filter.is_csp() {
+        // A CSP upgrading insecure requests is approximated by upgrading the
+        // matching requests themselves.
+        return match filter.csp.as_deref() {
+            Some("upgrade-insecure-requests") if !view.exception => {
+                let priority = if view.important {
+                    DNR_IMPORTANT_PRIORITY
+                } else {
+                    DNR_BLOCK_PRIORITY
+                };
+                add_resource_types(&mut condition, &types);
+                let action = json!({ "type": "upgradeScheme" });
+                Ok(vec![dnr_rule(priority, action, condition)])
+            }
+            _ => Err("csp_unsupported"),
+        };
+    }
+
+    if let Some(redirect) = &filter.redirect {
+        if view.exception {
+            return Err("redirect_exception_unsupported");
+        }
+        let target = if filter.is_redirect_url() {
+            json!({ "url": redirect })
+        } else if filter.also_block_redirect() {
+            json!({ "extensionPath": format!("{}{}", resource_path, redirect) })
+        } else {
+            // `$redirect-rule` filters only redirect requests blocked by
+            // another filter.
+            return Err("redirect_rule_unsupported");
+        };
+        let priority = if view.important {
+            DNR_IMPORTANT_REDIRECT_PRIORITY
+        } else {
+            DNR_REDIRECT_PRIORITY
+        };
+        add_resource_types(&mut condition, &types);
+        let action = json!({ "type": "redirect", "redirect": target });
+        return Ok(vec![dnr_rule(priority, action, condition)]);
+    }
+
+    if !view.exception {
+        let priority = if view.important {
+            DNR_IMPORTANT_PRIORITY
+        } else {
+            DNR_BLOCK_PRIORITY
+        };
+        add_resource_types(&mut condition, &types);
+        let action = json!({ "type": "block" });
+        return Ok(vec![dnr_rule(priority, action, condition)]);
+    }
+
+    // `$document` exceptions allow every request made by the pages they
+    // match, and the remaining request types are allowed as usual. Other
+    // exceptions only apply to the requests they match, including documents,
+    // like they do in the engine.
+    if !filter::has_request_type_option(text, "document") {
+        add_resource_types(&mut condition, &types);
+        let action = json!({ "type": "allow" });
+        return Ok(vec![dnr_rule(DNR_EXCEPTION_PRIORITY, action, condition)]);
+    }
+    let mut rules = Vec::new();
+    let others: Vec<&str> = types
+        .iter()
+        .copied()
+        .filter(|resource_type| *resource_type != "main_frame")
+        .collect();
+    if others.len() < types.len() {
+        let mut document_condition = condition.clone();
+        document_condition.insert("resourceTypes".into(), json!(["main_frame"]));
+        let action = json!({ "type": "allowAllRequests" });
+        rules.push(dnr_rule(DNR_EXCEPTION_PRIORITY, action, document_condition));
+    }
+    if !others.is_empty() {
+        add_resource_types(&mut condition, &others);
+        let action = json!({ "type": "allow" });
+        rules.push(dnr_rule(DNR_EXCEPTION_PRIORITY, action, condition));
+    }
+    Ok(rules)
+}
+
+/// Converts the network filters of a `FilterSet` into `declarativeNetRequest`
+/// rules, keeping track of the filters that could not be converted.
+///
+/// Rules are numbered from 1. Exceptions come first, so that filters dropped
+/// because of `max_rules` or `max_regex_rules` are never exceptions
+/// overriding filters that were kept.
+pub(crate) fn to_declarative_net_request(
+    network_filters: &[NetworkFilter],
+    network_origins: &[FilterOrigin],
+    max_rules: Option<usize>,
+    max_regex_rules: Option<usize>,
+    resource_path: &str,
+) -> PyResult<DeclarativeNetRequestRules> {
+    let mut dropped: Vec<(usize, DroppedFilter)> = Vec::new();
+    let mut converted: Vec<(usize, Vec<Value>)> = Vec::new();
+
+    let badfiltered = badfiltered_ids(network_filters);
+    for (index, (filter, origin)) in network_filters.iter().zip(network_origins).enumerate() {
+        let text = filter_text(filter)?;
+        if filter.is_badfilter() {
+            continue;
+        }
+        let kind = if badfiltered.contains(&filter.get_id()) {
+            "badfiltered"
+        } else {
+            match dnr_rules(filter, text, resource_path) {
+                Ok(rules) => {
+                    converted.push((index, rules));
+                    continue;
+                }
+                Err(kind) => kind,
+            }
+        };
+        dropped.push((index, DroppedFilter::new(text, "network", kind, origin)));
+    }
+
+    converted.sort_by_key(|(index, _)| !network_filters[*index].is_exception());
+    let mut rules: Vec<Value> = Vec::new();
+    let mut regex_rule_count = 0;
+    for (index, filter_rules) in converted {
+        let is_regex = network_filters[index].is_complete_regex();
+        let kind = if matches!(max_rules, Some(max) if rules.len() + filter_rules.len() > max) {
+            "rule_limit_exceeded"
+        } else if is_regex
+            && matches!(max_regex_rules, Some(max) if regex_rule_count + filter_rules.len() > max)
+        {
+            "regex_limit_exceeded"
+        } else {
+            for mut rule in filter_rules {
+                rule["id"] = json!(rules.len() + 1);
+                rules.push(rule);
+                if is_regex {
+                    regex_rule_count += 1;
+                }
+            }
+            continue;
+        };
+        let text = filter_text(&network_filters[index])?;
+        let dropped_filter = DroppedFilter::new(text, "network", kind, &network_origins[index]);
+        dropped.push((index, dropped_filter));
+    }
+    dropped.sort_by_key(|(index, _)| *index);
+
+    let json = serde_json::to_string(&rules).expect("declarativeNetRequest rules are valid JSON");
+    Ok(DeclarativeNetRequestRules {
+        json,
+        rule_count: rules.len(),
+        regex_rule_count,
+        dropped: dropped.into_iter().map(|(_, filter)| filter).collect(),
+    })
+}
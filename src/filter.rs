@@ -0,0 +1,502 @@
+//! Python views of single parsed filter rules, returned by `parse_filter`.
+
+use adblock::filters::cosmetic::{CosmeticFilter as RustCosmeticFilter, CosmeticFilterMask};
+use adblock::filters::network::{NetworkFilter as RustNetworkFilter, NetworkFilterMask};
+use pyo3::prelude::*;
+
+use std::collections::HashSet;
+
+use crate::DiyPythonRepr;
+
+/// The request types a network filter can apply to, by the name of the
+/// option enabling them.
+const REQUEST_TYPE_MASKS: &[(&str, NetworkFilterMask)] = &[
+    ("document", NetworkFilterMask::FROM_DOCUMENT),
+    ("font", NetworkFilterMask::FROM_FONT),
+    ("image", NetworkFilterMask::FROM_IMAGE),
+    ("media", NetworkFilterMask::FROM_MEDIA),
+    ("object", NetworkFilterMask::FROM_OBJECT),
+    ("other", NetworkFilterMask::FROM_OTHER),
+    ("ping", NetworkFilterMask::FROM_PING),
+    ("script", NetworkFilterMask::FROM_SCRIPT),
+    ("stylesheet", NetworkFilterMask::FROM_STYLESHEET),
+    ("subdocument", NetworkFilterMask::FROM_SUBDOCUMENT),
+    ("websocket", NetworkFilterMask::FROM_WEBSOCKET),
+    ("xmlhttprequest", NetworkFilterMask::FROM_XMLHTTPREQUEST),
+];
+
+/// A parsed network filter rule, such as `||ads.example.com^$script`.
+#[pyclass]
+#[derive(Clone)]
+pub struct NetworkFilter {
+    /// The parsed upstream filter.
+    pub filter: RustNetworkFilter,
+    /// The text of the rule, as given.
+    #[pyo3(get)]
+    pub text: String,
+    /// Whether the rule is an exception, starting with `@@`.
+    #[pyo3(get)]
+    pub exception: bool,
+    /// The pattern URLs are matched against, as written, without the
+    /// anchors and options.
+    #[pyo3(get)]
+    pub pattern: String,
+    /// Whether the pattern is a regular expression, like `/banner[0-9]+/`.
+    #[pyo3(get)]
+    pub is_regex: bool,
+    /// Whether the pattern must match at the start of the URL (`|`).
+    #[pyo3(get)]
+    pub left_anchor: bool,
+    /// Whether the pattern must match at the start of the hostname (`||`).
+    #[pyo3(get)]
+    pub hostname_anchor: bool,
+    /// Whether the pattern must match at the end of the URL (`|`).
+    #[pyo3(get)]
+    pub right_anchor: bool,
+    /// The domains in the `$domain` option the rule is restricted to.
+    #[pyo3(get)]
+    pub domains: HashSet<String>,
+    /// The domains excluded with `~` in the `$domain` option.
+    #[pyo3(get)]
+    pub excluded_domains: HashSet<String>,
+    /// The request types the rule applies to, such as `"script"`.
+    #[pyo3(get)]
+    pub request_types: HashSet<&'static str>,
+    /// Whether the rule applies to first-party requests.
+    #[pyo3(get)]
+    pub first_party: bool,
+    /// Whether the rule applies to third-party requests.
+    #[pyo3(get)]
+    pub third_party: bool,
+    /// Whether the rule has the `$important` option.
+    #[pyo3(get)]
+    pub important: bool,
+    /// Whether the rule has the `$match-case` option.
+    #[pyo3(get)]
+    pub match_case: bool,
+    /// Whether the rule has the `$badfilter` option.
+    #[pyo3(get)]
+    pub badfilter: bool,
+    /// Whether the rule has the `$generichide` option.
+    #[pyo3(get)]
+    pub generichide: bool,
+    /// The resource named by the `$redirect` or `$redirect-rule` option.
+    #[pyo3(get)]
+    pub redirect: Option<String>,
+    /// The value of the `$tag` option.
+    #[pyo3(get)]
+    pub tag: Option<String>,
+    /// The policy of the `$csp` option.
+    #[pyo3(get)]
+    pub csp: Option<String>,
+}
+
+/// The parts of the text of a network filter rule, split up like the
+/// upstream parser does.
+struct NetworkRule<'a> {
+    exception: bool,
+    left_anchor: bool,
+    hostname_anchor: bool,
+    pattern: &'a str,
+    right_anchor: bool,
+    /// The options after the last `$`, split at commas.
+    options: Option<Vec<&'a str>>,
+}
+
+/// The domains of the `$domain` options of a rule. Like upstream, a later
+/// option replaces the included or excluded domains of an earlier one.
+#[derive(Default)]
+struct Domains<'a> {
+    included: Vec<&'a str>,
+    excluded: Vec<&'a str>,
+}
+
+impl<'a> NetworkRule<'a> {
+    fn split(text: &'a str) -> Self {
+        let mut pattern = text;
+        let exception = pattern.starts_with("@@");
+        if exception {
+            pattern = &pattern[2..];
+        }
+        let mut options = None;
+        if let Some(index) = pattern.rfind('$') {
+            options = Some(pattern[index + 1..].split(',').collect());
+            pattern = &pattern[..index];
+        }
+        let hostname_anchor = pattern.starts_with("||");
+        let left_anchor = !hostname_anchor && pattern.starts_with('|');
+        if hostname_anchor {
+            pattern = &pattern[2..];
+        } else if left_anchor {
+            pattern = &pattern[1..];
+        }
+        let right_anchor = !pattern.is_empty() && pattern.ends_with('|');
+        if right_anchor {
+            pattern = &pattern[..pattern.len() - 1];
+        }
+        Self {
+            exception,
+            left_anchor,
+            hostname_anchor,
+            pattern,
+            right_anchor,
+            options,
+        }
+    }
+
+    fn domains(&self) -> Domains<'a> {
+        let mut domains = Domains::default();
+        let values = self
+            .options
+            .iter()
+            .flatten()
+            .filter_map(|option| option.strip_prefix("domain="));
+        for value in values {
+            let (excluded, included): (Vec<&str>, Vec<&str>) =
+                value.split('|').partition(|domain| domain.starts_with('~'));
+            if !included.is_empty() {
+                domains.included = included;
+            }
+            if !excluded.is_empty() {
+                domains.excluded = excluded.iter().map(|domain| &domain[1..]).collect();
+            }
+        }
+        domains
+    }
+}
+
+impl NetworkFilter {
+    pub(crate) fn new(filter: RustNetworkFilter, text: &str) -> Self {
+        let text = text.trim();
+        let rule = NetworkRule::split(text);
+        let domains = rule.domains();
+        let mask = filter.mask;
+        Self {
+            text: text.to_string(),
+            exception: rule.exception,
+            pattern: rule.pattern.to_string(),
+            is_regex: filter.is_complete_regex(),
+            left_anchor: rule.left_anchor,
+            hostname_anchor: rule.hostname_anchor,
+            right_anchor: rule.right_anchor,
+            domains: domains.included.into_iter().map(str::to_string).collect(),
+            excluded_domains: domains.excluded.into_iter().map(str::to_string).collect(),
+            request_types: REQUEST_TYPE_MASKS
+                .iter()
+                .filter(|(_, flag)| mask.contains(*flag))
+                .map(|(name, _)| *name)
+                .collect(),
+            first_party: mask.contains(NetworkFilterMask::FIRST_PARTY),
+            third_party: mask.contains(NetworkFilterMask::THIRD_PARTY),
+            important: filter.is_important(),
+            match_case: mask.contains(NetworkFilterMask::MATCH_CASE),
+            badfilter: filter.is_badfilter(),
+            generichide: filter.is_generic_hide(),
+            redirect: filter.redirect.clone(),
+            tag: filter.tag.clone(),
+            csp: filter.csp.clone(),
+            filter,
+        }
+    }
+}
+
+#[pymethods]
+impl NetworkFilter {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!("NetworkFilter({})", self.text.diy_python_repr()))
+    }
+}
+
+/// A parsed cosmetic filter rule, such as `example.com##.banner` or
+/// `example.com##+js(nobab)`.
+#[pyclass]
+#[derive(Clone)]
+pub struct CosmeticFilter {
+    /// The parsed upstream filter.
+    pub filter: RustCosmeticFilter,
+    /// The text of the rule, as given.
+    #[pyo3(get)]
+    pub text: String,
+    /// Whether the rule is an exception, using `#@#`.
+    #[pyo3(get)]
+    pub exception: bool,
+    /// The hostnames the rule is restricted to. Entities such as
+    /// `example.*` are included as written.
+    #[pyo3(get)]
+    pub hostnames: HashSet<String>,
+    /// The hostnames excluded with `~`.
+    #[pyo3(get)]
+    pub excluded_hostnames: HashSet<String>,
+    /// The CSS selector of the rule, or `None` for scriptlet rules.
+    #[pyo3(get)]
+    pub selector: Option<String>,
+    /// The declarations in the `:style()` of the rule.
+    #[pyo3(get)]
+    pub style: Option<String>,
+    /// The name of the scriptlet injected by a `+js()` rule.
+    #[pyo3(get)]
+    pub scriptlet: Option<String>,
+    /// The arguments passed to the scriptlet.
+    #[pyo3(get)]
+    pub scriptlet_args: Vec<String>,
+}
+
+impl CosmeticFilter {
+    pub(crate) fn new(filter: RustCosmeticFilter, text: &str) -> Self {
+        let text = text.trim();
+        let mut hostnames = HashSet::new();
+        let mut excluded_hostnames = HashSet::new();
+        let locations = text.find('#').map_or("", |index| &text[..index]);
+        for location in locations.split(',').filter(|location| !location.is_empty()) {
+            match location.strip_prefix('~') {
+                Some(location) => excluded_hostnames.insert(location.to_string()),
+                None => hostnames.insert(location.to_string()),
+            };
+        }
+
+        let (selector, scriptlet, scriptlet_args) =
+            if filter.mask.contains(CosmeticFilterMask::SCRIPT_INJECT) {
+                let mut args = scriptlet_args(&filter.selector).into_iter();
+                (None, args.next(), args.collect())
+            } else {
+                (Some(filter.selector.clone()), None, Vec::new())
+            };
+
+        Self {
+            text: text.to_string(),
+            exception: filter.mask.contains(CosmeticFilterMask::UNHIDE),
+            hostnames,
+            excluded_hostnames,
+            selector,
+            style: filter.style.clone(),
+            scriptlet,
+            scriptlet_args,
+            filter,
+        }
+    }
+}
+
+#[pymethods]
+impl CosmeticFilter {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!("CosmeticFilter({})", self.text.diy_python_repr()))
+    }
+}
+
+/// Splits the contents of `+js(...)` at commas not escaped as `\,`, and trims
+/// the parts, like the engine does when injecting the scriptlet.
+fn split_scriptlet_args(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (index, _) in args.match_indices(',') {
+        if args[..index].ends_with('\\') {
+            continue;
+        }
+        parts.push(args[start..index].trim());
+        start = index + 1;
+    }
+    if start != args.len() {
+        parts.push(args[start..].trim());
+    }
+    parts
+}
+
+/// Splits the contents of `+js(...)` into the scriptlet name and its
+/// arguments. Backslashes and quotes are removed, as they are by the engine.
+fn scriptlet_args(args: &str) -> Vec<String> {
+    split_scriptlet_args(args)
+        .into_iter()
+        .map(|arg| arg.replace(['\\', '"', '\''], ""))
+        .collect()
+}
+
+/// Returns the canonical name of a request type option, resolving aliases
+/// such as `xhr`.
+fn canonical_request_type(option: &str) -> Option<&'static str> {
+    Some(match option {
+        "document" | "doc" => "document",
+        "font" => "font",
+        "image" => "image",
+        "media" => "media",
+        "object" | "object-subrequest" => "object",
+        "other" => "other",
+        "ping" | "beacon" => "ping",
+        "script" => "script",
+        "stylesheet" | "css" => "stylesheet",
+        "subdocument" | "frame" => "subdocument",
+        "websocket" => "websocket",
+        "xmlhttprequest" | "xhr" => "xmlhttprequest",
+        _ => return None,
+    })
+}
+
+/// Whether the network filter rule `text` enables `request_type` with an
+/// option of its own, such as `$doc` for `"document"`, rather than applying
+/// to it by default.
+pub(crate) fn has_request_type_option(text: &str, request_type: &str) -> bool {
+    NetworkRule::split(text.trim())
+        .options
+        .iter()
+        .flatten()
+        .any(|option| canonical_request_type(option) == Some(request_type))
+}
+
+/// Sorts and deduplicates `values`.
+fn sorted<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut values: Vec<&str> = values.into_iter().collect();
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+/// Formats a network filter, parsed from `text`, in its canonical form.
+///
+/// Option aliases are replaced by their full names, such as `third-party`
+/// for `3p` and `xmlhttprequest` for `xhr`, and options are written in a
+/// fixed order: request types, then negated request types, the party,
+/// `domain`, flags such as `important`, and options with values. `$domain`
+/// values are sorted, and the pattern is lowercased unless it is a regular
+/// expression or the rule has `$match-case`. Parsing the canonical form gives
+/// a filter with the same meaning.
+pub(crate) fn canonical_network(filter: &RustNetworkFilter, text: &str) -> String {
+    let rule = NetworkRule::split(text.trim());
+    let mut canonical = String::new();
+    if rule.exception {
+        canonical.push_str("@@");
+    }
+    if rule.hostname_anchor {
+        canonical.push_str("||");
+    } else if rule.left_anchor {
+        canonical.push('|');
+    }
+    let mask = filter.mask;
+    if filter.is_complete_regex() || mask.contains(NetworkFilterMask::MATCH_CASE) {
+        canonical.push_str(rule.pattern);
+    } else {
+        canonical.push_str(&rule.pattern.to_ascii_lowercase());
+    }
+    if rule.right_anchor {
+        canonical.push('|');
+    }
+
+    let raw_options = match &rule.options {
+        Some(options) => options,
+        None => return canonical,
+    };
+    let mut options: Vec<String> = Vec::new();
+
+    let request_types = |negated: bool| {
+        raw_options
+            .iter()
+            .filter(move |option| option.starts_with('~') == negated)
+            .filter_map(|option| canonical_request_type(option.trim_start_matches('~')))
+    };
+    options.extend(sorted(request_types(false)).into_iter().map(str::to_string));
+    options.extend(
+        sorted(request_types(true))
+            .into_iter()
+            .map(|name| format!("~{}", name)),
+    );
+
+    // The party options only clear bits of the mask.
+    if !mask.contains(NetworkFilterMask::THIRD_PARTY) {
+        options.push("first-party".into());
+    }
+    if !mask.contains(NetworkFilterMask::FIRST_PARTY) {
+        options.push("third-party".into());
+    }
+
+    let domains = rule.domains();
+    let domains: Vec<String> = sorted(domains.included)
+        .into_iter()
+        .map(str::to_string)
+        .chain(
+            sorted(domains.excluded)
+                .into_iter()
+                .map(|domain| format!("~{}", domain)),
+        )
+        .collect();
+    if !domains.is_empty() {
+        options.push(format!("domain={}", domains.join("|")));
+    }
+
+    let flags = [
+        (NetworkFilterMask::IS_IMPORTANT, "important"),
+        (NetworkFilterMask::MATCH_CASE, "match-case"),
+        (NetworkFilterMask::GENERIC_HIDE, "generichide"),
+        (NetworkFilterMask::BAD_FILTER, "badfilter"),
+    ];
+    for (flag, name) in flags.iter() {
+        if mask.contains(*flag) {
+            options.push(name.to_string());
+        }
+    }
+    if raw_options.contains(&"collapse") || raw_options.contains(&"~collapse") {
+        options.push("collapse".into());
+    }
+
+    if filter.is_csp() {
+        options.push(match &filter.csp {
+            Some(csp) => format!("csp={}", csp),
+            None => "csp".into(),
+        });
+    }
+    if let Some(redirect) = &filter.redirect {
+        let option = if mask.contains(NetworkFilterMask::ALSO_BLOCK_REDIRECT) {
+            "redirect"
+        } else if mask.contains(NetworkFilterMask::IS_REDIRECT_URL) {
+            "redirect-url"
+        } else {
+            "redirect-rule"
+        };
+        options.push(format!("{}={}", option, redirect));
+    }
+    if let Some(tag) = &filter.tag {
+        options.push(format!("tag={}", tag));
+    }
+    if let Some(bug) = filter.bug {
+        options.push(format!("bug={}", bug));
+    }
+
+    canonical.push('$');
+    canonical.push_str(&options.join(","));
+    canonical
+}
+
+/// Formats a cosmetic filter, parsed from `text`, in its canonical form.
+///
+/// Hostnames are sorted and deduplicated, with excluded hostnames last, and
+/// the arguments of scriptlets are separated by `", "`.
+pub(crate) fn canonical_cosmetic(filter: &RustCosmeticFilter, text: &str) -> String {
+    let text = text.trim();
+    let sharp_index = text.find('#').unwrap_or(0);
+    let locations = text[..sharp_index]
+        .split(',')
+        .filter(|location| !location.is_empty());
+    let (excluded, included): (Vec<&str>, Vec<&str>) =
+        locations.partition(|location| location.starts_with('~'));
+    let mut canonical = sorted(included)
+        .into_iter()
+        .chain(sorted(excluded))
+        .collect::<Vec<&str>>()
+        .join(",");
+
+    canonical.push_str(if filter.mask.contains(CosmeticFilterMask::UNHIDE) {
+        "#@#"
+    } else {
+        "##"
+    });
+    if filter.mask.contains(CosmeticFilterMask::SCRIPT_INJECT) {
+        canonical.push_str("+js(");
+        canonical.push_str(&split_scriptlet_args(&filter.selector).join(", "));
+        canonical.push(')');
+    } else {
+        canonical.push_str(&filter.selector);
+        if let Some(style) = &filter.style {
+            canonical.push_str(":style(");
+            canonical.push_str(style);
+            canonical.push(')');
+        }
+    }
+    canonical
+}
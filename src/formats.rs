@@ -0,0 +1,406 @@
+//! The formats and rule types filter lists can be added with. Formats beyond
+//! the ones `adblock::lists` can parse are converted into equivalent network
+//! filters.
+
+use adblock::lists::FilterFormat as RustFilterFormat;
+use adblock::lists::{ParseOptions, RuleTypes as RustRuleTypes};
+use pyo3::prelude::*;
+
+use std::borrow::Cow;
+use std::net::IpAddr;
+
+use crate::{DiyPythonRepr, InvalidOptionError};
+
+/// How many filter rules `detect` looks at.
+const DETECT_LINES: usize = 100;
+
+/// Options of AdGuard DNS filters restricting them to some clients or DNS
+/// record types. A network filter can't express that restriction, so such
+/// filters are rejected instead of applying to every request.
+const DNS_ONLY_OPTIONS: &[&str] = &["client", "ctag", "dnstype"];
+
+/// Builds the `InvalidOptionError` for an option string that isn't one of
+/// `allowed`.
+fn invalid_option(option: &str, value: &str, allowed: &[&'static str]) -> PyErr {
+    let allowed: Vec<String> = allowed
+        .iter()
+        .map(|value| value.to_string().diy_python_repr())
+        .collect();
+    InvalidOptionError::new_err(format!(
+        "Invalid {} {}, expected one of {}",
+        option,
+        value.to_string().diy_python_repr(),
+        allowed.join(", ")
+    ))
+}
+
+/// The format of a filter list, mirroring `adblock::lists::FilterFormat`.
+/// Anywhere a format is expected, either a `FilterFormat` or its string value
+/// can be given.
+#[pyclass]
+#[allow(non_camel_case_types)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum FilterFormat {
+    /// ABP/uBO-style filters.
+    STANDARD,
+    /// Hosts files, such as `0.0.0.0 example.com`.
+    HOSTS,
+    /// One domain per line, such as `example.com` or `*.example.com`.
+    DOMAINS,
+    /// dnsmasq configuration, such as `address=/example.com/0.0.0.0` or
+    /// `server=/example.com/`.
+    DNSMASQ,
+    /// AdGuard Home DNS filters, such as `||example.com^$client=10.0.0.1`.
+    ADGUARD_DNS,
+    /// Any of the above, detected from the first rules of the list.
+    AUTO,
+}
+
+impl FilterFormat {
+    const VALUES: &'static [&'static str] = &[
+        "standard",
+        "hosts",
+        "domains",
+        "dnsmasq",
+        "adguard_dns",
+        "auto",
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::STANDARD => "standard",
+            Self::HOSTS => "hosts",
+            Self::DOMAINS => "domains",
+            Self::DNSMASQ => "dnsmasq",
+            Self::ADGUARD_DNS => "adguard_dns",
+            Self::AUTO => "auto",
+        }
+    }
+
+    fn from_str(format: &str) -> Option<Self> {
+        match format {
+            "standard" => Some(Self::STANDARD),
+            "hosts" => Some(Self::HOSTS),
+            "domains" => Some(Self::DOMAINS),
+            "dnsmasq" => Some(Self::DNSMASQ),
+            "adguard_dns" => Some(Self::ADGUARD_DNS),
+            "auto" => Some(Self::AUTO),
+            _ => None,
+        }
+    }
+
+    /// Returns this format, or the format of `lines` if it is `AUTO`.
+    pub(crate) fn resolve<'a>(self, lines: impl IntoIterator<Item = &'a str>) -> Self {
+        match self {
+            Self::AUTO => detect(lines),
+            format => format,
+        }
+    }
+
+    /// Converts a line of a list in this format into rules `parse_filter`
+    /// understands. `AUTO` must have been resolved already.
+    pub(crate) fn convert(self, line: &str) -> Converted<'_> {
+        match self {
+            Self::STANDARD | Self::AUTO => Converted::Rule(RustFilterFormat::Standard, line.into()),
+            Self::HOSTS => Converted::Rule(RustFilterFormat::Hosts, line.into()),
+            Self::DOMAINS => convert_domain(line.trim()),
+            Self::DNSMASQ => convert_dnsmasq(line),
+            Self::ADGUARD_DNS => convert_adguard_dns(line),
+        }
+    }
+}
+
+#[pymethods]
+impl FilterFormat {
+    /// The string value of this format.
+    #[getter]
+    pub fn value(&self) -> &'static str {
+        self.as_str()
+    }
+}
+
+/// A format argument, given either as a `FilterFormat` or as a string.
+#[derive(FromPyObject)]
+pub enum FilterFormatArg<'a> {
+    #[pyo3(transparent, annotation = "FilterFormat")]
+    Enum(FilterFormat),
+    #[pyo3(transparent, annotation = "str")]
+    Str(&'a str),
+}
+
+impl FilterFormatArg<'_> {
+    /// Returns the format, raising `InvalidOptionError` for unknown strings.
+    pub(crate) fn resolve(&self) -> PyResult<FilterFormat> {
+        match *self {
+            Self::Enum(format) => Ok(format),
+            Self::Str(format) => FilterFormat::from_str(format)
+                .ok_or_else(|| invalid_option("format", format, FilterFormat::VALUES)),
+        }
+    }
+}
+
+/// The kinds of rules to keep when adding a filter list, mirroring
+/// `adblock::lists::RuleTypes`. Anywhere rule types are expected, either a
+/// `RuleTypes` or its string value can be given.
+#[pyclass]
+#[allow(non_camel_case_types)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum RuleTypes {
+    /// Both network and cosmetic filters.
+    ALL,
+    /// Only network filters.
+    NETWORK_ONLY,
+    /// Only cosmetic filters.
+    COSMETIC_ONLY,
+}
+
+impl RuleTypes {
+    const VALUES: &'static [&'static str] = &["all", "networkonly", "cosmeticonly"];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::ALL => "all",
+            Self::NETWORK_ONLY => "networkonly",
+            Self::COSMETIC_ONLY => "cosmeticonly",
+        }
+    }
+
+    fn from_str(rule_types: &str) -> Option<Self> {
+        match rule_types {
+            "all" => Some(Self::ALL),
+            "networkonly" => Some(Self::NETWORK_ONLY),
+            "cosmeticonly" => Some(Self::COSMETIC_ONLY),
+            _ => None,
+        }
+    }
+}
+
+impl From<RuleTypes> for RustRuleTypes {
+    fn from(rule_types: RuleTypes) -> Self {
+        match rule_types {
+            RuleTypes::ALL => Self::All,
+            RuleTypes::NETWORK_ONLY => Self::NetworkOnly,
+            RuleTypes::COSMETIC_ONLY => Self::CosmeticOnly,
+        }
+    }
+}
+
+#[pymethods]
+impl RuleTypes {
+    /// The string value of these rule types.
+    #[getter]
+    pub fn value(&self) -> &'static str {
+        self.as_str()
+    }
+}
+
+/// A rule types argument, given either as a `RuleTypes` or as a string.
+#[derive(FromPyObject)]
+pub enum RuleTypesArg<'a> {
+    #[pyo3(transparent, annotation = "RuleTypes")]
+    Enum(RuleTypes),
+    #[pyo3(transparent, annotation = "str")]
+    Str(&'a str),
+}
+
+impl RuleTypesArg<'_> {
+    /// Returns the rule types, raising `InvalidOptionError` for unknown
+    /// strings.
+    pub(crate) fn resolve(&self) -> PyResult<RuleTypes> {
+        match *self {
+            Self::Enum(rule_types) => Ok(rule_types),
+            Self::Str(rule_types) => RuleTypes::from_str(rule_types)
+                .ok_or_else(|| invalid_option("rule_types", rule_types, RuleTypes::VALUES)),
+        }
+    }
+}
+
+/// The rules a line of a filter list was converted into.
+pub(crate) enum Converted<'a> {
+    /// A rule, and the format to parse it in.
+    Rule(RustFilterFormat, Cow<'a, str>),
+    /// Domains to block, parsed as hosts file entries.
+    Hostnames(Vec<&'a str>),
+    /// A comment, or a line without an equivalent filter.
+    Ignored,
+    /// A filter that can't be converted without changing what it blocks,
+    /// with the kind of error to report for it.
+    Rejected(&'static str),
+}
+
+/// The options of a call adding filters to a `FilterSet`.
+#[derive(Copy, Clone)]
+pub(crate) struct ListOptions {
+    pub format: FilterFormat,
+    pub include_redirect_urls: bool,
+    pub rule_types: RuleTypes,
+    /// Whether to keep the canonical form of each filter, instead of its
+    /// text.
+    pub canonicalize: bool,
+}
+
+impl ListOptions {
+    /// The options to parse a rule converted by `FilterFormat::convert` with.
+    pub fn parse_options(&self, format: RustFilterFormat) -> ParseOptions {
+        ParseOptions {
+            format,
+            include_redirect_urls: self.include_redirect_urls,
+            rule_types: self.rule_types.into(),
+        }
+    }
+}
+
+/// Converts `address=/domain/.../ip`, `server=/domain/.../` and
+/// `local=/domain/.../` lines. `address` lines only block the domains if they
+/// resolve to a null or loopback address, or to nothing, other addresses
+/// being local DNS overrides. `server` and `local` lines only block the
+/// domains if they aren't forwarded to another server.
+fn convert_dnsmasq(line: &str) -> Converted<'_> {
+    let (option, value) = match line.trim().split_once('=') {
+        Some(option) => option,
+        None => return Converted::Ignored,
+    };
+    let mut parts: Vec<&str> = match value.strip_prefix('/') {
+        Some(value) => value.split('/').collect(),
+        None => return Converted::Ignored,
+    };
+    // The part after the last `/` is the address or server.
+    let target = parts.pop().unwrap_or_default();
+    let blocks = match option.trim() {
+        "address" => matches!(
+            target.trim(),
+            "" | "#" | "0.0.0.0" | "::" | "127.0.0.1" | "::1"
+        ),
+        "server" | "local" => target.trim().is_empty(),
+        _ => false,
+    };
+    // `#` matches every domain, which is not something to block.
+    parts.retain(|domain| !domain.is_empty() && *domain != "#");
+    if !blocks || parts.is_empty() {
+        return Converted::Ignored;
+    }
+    Converted::Hostnames(parts)
+}
+
+/// Converts a bare domain, optionally followed by a `#` comment. Hosts
+/// parsing already accepts those, but not wildcards: `*.example.com` only
+/// blocks the subdomains of `example.com`, which `||.example.com^` matches.
+fn convert_domain(line: &str) -> Converted<'_> {
+    let domain = line.split('#').next().unwrap_or_default().trim();
+    match domain.strip_prefix("*.") {
+        Some(domain) => {
+            Converted::Rule(RustFilterFormat::Standard, format!("||.{}^", domain).into())
+        }
+        None => Converted::Rule(RustFilterFormat::Hosts, line.into()),
+    }
+}
+
+/// Converts an AdGuard DNS filter. Hosts file entries and bare domains are
+/// parsed as such, other filters are standard ones. Filters restricted to
+/// some clients or record types are rejected, see `DNS_ONLY_OPTIONS`.
+fn convert_adguard_dns(line: &str) -> Converted<'_> {
+    let filter = line.trim();
+    if filter.starts_with('!') || filter.starts_with('#') {
+        return Converted::Ignored;
+    }
+    if is_hosts_entry(filter) {
+        return Converted::Rule(RustFilterFormat::Hosts, filter.into());
+    }
+    if is_domain(filter) {
+        return convert_domain(filter);
+    }
+    let options = match filter.rsplit_once('$') {
+        // A `$` at the end of a regex is part of the pattern.
+        Some((_, options)) if !options.ends_with('/') => options,
+        _ => return Converted::Rule(RustFilterFormat::Standard, filter.into()),
+    };
+    for option in options.split(',') {
+        let name = option.split('=').next().unwrap_or_default().trim();
+        if name == "dnsrewrite" {
+            // Rewritten responses can't be expressed as a network filter.
+            return Converted::Ignored;
+        }
+        if DNS_ONLY_OPTIONS.contains(&name) {
+            return Converted::Rejected("dns_only_option");
+        }
+    }
+    Converted::Rule(RustFilterFormat::Standard, filter.into())
+}
+
+/// Whether `line` is a comment in any of the formats.
+fn is_comment(line: &str) -> bool {
+    // `##`, `#@#`, `#?#` and the like start cosmetic filters.
+    line.starts_with('!')
+        || (line.starts_with('#') && !line[1..].starts_with(['#', '@', '?', '$', '%']))
+}
+
+fn is_hosts_entry(line: &str) -> bool {
+    let mut parts = line.split_whitespace();
+    matches!(
+        (parts.next().map(str::parse::<IpAddr>), parts.next()),
+        (Some(Ok(_)), Some(_))
+    )
+}
+
+/// Whether `line` is a bare domain with at least two labels, such as
+/// `example.com`, optionally followed by a `#` comment.
+fn is_domain(line: &str) -> bool {
+    let domain = line.split('#').next().unwrap_or_default().trim();
+    let domain = domain.strip_prefix("*.").unwrap_or(domain);
+    domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Detects the format of a filter list from its first rules, ignoring
+/// comments and empty lines.
+pub(crate) fn detect<'a>(lines: impl IntoIterator<Item = &'a str>) -> FilterFormat {
+    let mut hosts = 0;
+    let mut domains = 0;
+    let mut others = 0;
+    let rules = lines
+        .into_iter()
+        .map(|line| line.trim_start_matches('\u{FEFF}').trim())
+        .filter(|line| !line.is_empty() && !is_comment(line))
+        .take(DETECT_LINES);
+    for line in rules {
+        if line.starts_with("[Adblock") {
+            return FilterFormat::STANDARD;
+        }
+        if ["address=/", "server=/", "local=/"]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+        {
+            return FilterFormat::DNSMASQ;
+        }
+        if let Some((_, options)) = line.rsplit_once('$') {
+            let is_dns_option = |option: &str| {
+                let name = option.split('=').next().unwrap_or_default().trim();
+                name == "dnsrewrite" || DNS_ONLY_OPTIONS.contains(&name)
+            };
+            if options.split(',').any(is_dns_option) {
+                return FilterFormat::ADGUARD_DNS;
+            }
+        }
+        if is_hosts_entry(line) {
+            hosts += 1;
+        } else if is_domain(line) {
+            domains += 1;
+        } else {
+            others += 1;
+        }
+    }
+    if others > 0 {
+        FilterFormat::STANDARD
+    } else if hosts > 0 {
+        // Hosts parsing also accepts bare domains.
+        FilterFormat::HOSTS
+    } else if domains > 0 {
+        FilterFormat::DOMAINS
+    } else {
+        FilterFormat::STANDARD
+    }
+}
@@ -12,26 +12,61 @@
 )]
 
 use adblock::blocker::BlockerResult as RustBlockerResult;
+use adblock::blocker::{Blocker, BlockerOptions};
 use adblock::blocker::{BlockerError as RustBlockerError, Redirection};
 use adblock::cosmetic_filter_cache::UrlSpecificResources as RustUrlSpecificResources;
 use adblock::engine::Engine as RustEngine;
+use adblock::filters::cosmetic::{CosmeticFilter as RustCosmeticFilter, CosmeticFilterMask};
+use adblock::filters::network::{NetworkFilter as RustNetworkFilter, NetworkMatchable};
+use adblock::lists::FilterFormat as RustFilterFormat;
+use adblock::lists::FilterParseError as RustFilterParseError;
 use adblock::lists::FilterSet as RustFilterSet;
-use adblock::lists::{FilterFormat, ParseOptions, RuleTypes};
+use adblock::lists::{parse_filter as parse_rust_filter, ParseOptions, ParsedFilter};
+use adblock::request::Request as RustRequest;
+use adblock::request::RequestType as RustRequestType;
+use adblock::url_parser::parse_url;
+use adblock::utils::{fast_hash, Hash as FilterId};
+use flate2::bufread::MultiGzDecoder;
+use pyo3::basic::CompareOp;
 use pyo3::create_exception;
 use pyo3::exceptions::PyException;
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
+use pyo3::types::{PyBytes, PyDict};
 use pyo3::PyErr;
+use serde::{Deserialize, Serialize};
 
 use adblock::resources::{
     AddResourceError as RustAddResourceError, MimeType, Resource, ResourceType,
 };
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::error::Error;
 use std::fmt::{self, Display};
 use std::fs;
-use std::io::{Read, Write};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+mod explain;
+
+use explain::{ExplainedFilter, Explanation, FilterLists, FilterMatches};
+mod export;
+use export::{ContentBlockingRules, DeclarativeNetRequestRules, DroppedFilter};
+mod filter;
+use filter::{CosmeticFilter, NetworkFilter};
+mod formats;
+use formats::{Converted, ListOptions};
+pub use formats::{FilterFormat, FilterFormatArg, RuleTypes, RuleTypesArg};
+mod lint;
+use lint::LintFinding;
+mod metadata;
+use metadata::FilterListMetadata;
+mod preprocess;
+use preprocess::{IncludeResolver, Preprocessor};
+mod report;
+use report::{ParseReport, RejectedFilter};
 
 /// Brave's adblocking library in Python!
 #[pymodule]
@@ -39,8 +74,28 @@ fn adblock(py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add("__version__", env!("CARGO_PKG_VERSION"))?;
     m.add_class::<Engine>()?;
     m.add_class::<FilterSet>()?;
+    m.add_class::<FilterSetStats>()?;
+    m.add_class::<FilterFormat>()?;
+    m.add_class::<RuleTypes>()?;
     m.add_class::<BlockerResult>()?;
+    m.add_class::<BlockerAction>()?;
     m.add_class::<UrlSpecificResources>()?;
+    m.add_class::<Request>()?;
+    m.add_class::<RequestType>()?;
+    m.add_class::<Explanation>()?;
+    m.add_class::<ExplainedFilter>()?;
+    m.add_class::<FilterMatches>()?;
+    m.add_class::<ParseReport>()?;
+    m.add_class::<RejectedFilter>()?;
+    m.add_class::<FilterListMetadata>()?;
+    m.add_class::<LintFinding>()?;
+    m.add_class::<NetworkFilter>()?;
+    m.add_class::<CosmeticFilter>()?;
+    m.add_class::<ContentBlockingRules>()?;
+    m.add_class::<DroppedFilter>()?;
+    m.add_class::<DeclarativeNetRequestRules>()?;
+    m.add_function(wrap_pyfunction!(parse_filter, m)?)?;
+    m.add_function(wrap_pyfunction!(normalize_filter, m)?)?;
     m.add("AdblockException", py.get_type::<AdblockException>())?;
     m.add("BlockerException", py.get_type::<BlockerException>())?;
     m.add("SerializationError", py.get_type::<SerializationError>())?;
@@ -69,11 +124,66 @@ fn adblock(py: Python<'_>, m: &PyModule) -> PyResult<()> {
         "InvalidUtf8ContentError",
         py.get_type::<InvalidUtf8ContentError>(),
     )?;
+    m.add("InvalidRequestError", py.get_type::<InvalidRequestError>())?;
+    m.add(
+        "InvalidRequestTypeError",
+        py.get_type::<InvalidRequestTypeError>(),
+    )?;
+    m.add("InvalidOptionError", py.get_type::<InvalidOptionError>())?;
+    m.add("FilterParseError", py.get_type::<FilterParseError>())?;
+    m.add("ConversionError", py.get_type::<ConversionError>())?;
+    m.add("InvalidUrlError", py.get_type::<InvalidUrlError>())?;
+    m.add(
+        "InvalidRequestUrlError",
+        py.get_type::<InvalidRequestUrlError>(),
+    )?;
+    m.add(
+        "InvalidSourceUrlError",
+        py.get_type::<InvalidSourceUrlError>(),
+    )?;
     Ok(())
 }
 
-/// The result of an ad-blocking check.
+/// What should be done with a request, as summarized by
+/// `BlockerResult.action`.
 #[pyclass]
+#[allow(non_camel_case_types)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BlockerAction {
+    /// No filter matched, the request should go ahead.
+    ALLOW,
+    /// The request should be blocked.
+    BLOCK,
+    /// The request should be blocked and answered with the contents of
+    /// `BlockerResult.redirect` instead.
+    REDIRECT,
+    /// An exception filter matched and no blocking filter overrides it, so
+    /// the request should go ahead. A blocking filter may not have matched,
+    /// such as with `force_check_exceptions`.
+    EXCEPTED,
+}
+
+#[pymethods]
+impl BlockerAction {
+    /// The lowercase name of this action.
+    #[getter]
+    pub fn value(&self) -> &'static str {
+        match self {
+            Self::ALLOW => "allow",
+            Self::BLOCK => "block",
+            Self::REDIRECT => "redirect",
+            Self::EXCEPTED => "excepted",
+        }
+    }
+}
+
+/// The result of an ad-blocking check.
+///
+/// `BlockerResult`s are immutable values: they compare equal when all of
+/// their fields are equal, can be hashed, pickled, and converted to a
+/// dictionary with `to_dict`.
+#[pyclass(module = "adblock")]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct BlockerResult {
     #[pyo3(get)]
     pub matched: bool,
@@ -95,6 +205,14 @@ pub struct BlockerResult {
     /// [1]: https://github.com/gorhill/uBlock/wiki/Static-filter-syntax#redirect
     #[pyo3(get)]
     pub redirect_type: Option<String>,
+    /// The name of the resource the request is redirected to, as given to
+    /// `Engine.add_resource`, if `redirect_type` is `"resource"`. `None` for
+    /// resources of an engine serialized by a version that didn't keep their
+    /// names. After `Engine.deserialize`, also `None` for a resource with the
+    /// same content as another one, since the filters naming them aren't
+    /// serialized.
+    #[pyo3(get)]
+    pub redirect_resource_name: Option<String>,
     /// Exception is not `None` when the blocker matched on an exception rule.
     /// Effectively this means that there was a match, but the request should
     /// not be blocked. It is a non-empty string if the blocker was initialized
@@ -119,6 +237,18 @@ pub struct BlockerResult {
     /// `check_network_urls` method.
     #[pyo3(get)]
     pub error: Option<String>,
+    /// The `source` of the list `filter` was added from, see
+    /// `FilterSet.add_filter_list`. `None` if the list was added without a
+    /// source, or if the engine was created from a `FilterSet` without
+    /// debugging enabled, since the filter is then not known. If `filter` was
+    /// added from several lists, or merged with other filters when optimizing
+    /// the engine, the sources of all of them are joined with `" <+> "`.
+    #[pyo3(get)]
+    pub filter_source: Option<String>,
+    /// The `source` of the list `exception` was added from, like
+    /// `filter_source`.
+    #[pyo3(get)]
+    pub exception_source: Option<String>,
 }
 
 impl From<RustBlockerResult> for BlockerResult {
@@ -139,22 +269,208 @@ impl From<RustBlockerResult> for BlockerResult {
             filter: br.filter,
             error: br.error,
             redirect_type,
+            redirect_resource_name: None,
             redirect,
+            filter_source: None,
+            exception_source: None,
         }
     }
 }
 
+impl BlockerResult {
+    /// Splits a resource redirect, which is a `data:` URL, into its MIME type
+    /// and base64 encoded body.
+    fn redirect_resource(&self) -> Option<(&str, &str)> {
+        if self.redirect_type.as_deref() != Some("resource") {
+            return None;
+        }
+        self.redirect
+            .as_deref()?
+            .strip_prefix("data:")?
+            .split_once(";base64,")
+    }
+}
+
 #[pymethods]
 impl BlockerResult {
+    #[new]
+    #[args(
+        matched = "false",
+        important = "false",
+        redirect = "None",
+        redirect_type = "None",
+        redirect_resource_name = "None",
+        exception = "None",
+        filter = "None",
+        error = "None",
+        filter_source = "None",
+        exception_source = "None"
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        matched: bool,
+        important: bool,
+        redirect: Option<String>,
+        redirect_type: Option<String>,
+        redirect_resource_name: Option<String>,
+        exception: Option<String>,
+        filter: Option<String>,
+        error: Option<String>,
+        filter_source: Option<String>,
+        exception_source: Option<String>,
+    ) -> Self {
+        Self {
+            matched,
+            important,
+            redirect_type,
+            redirect_resource_name,
+            redirect,
+            exception,
+            filter,
+            error,
+            filter_source,
+            exception_source,
+        }
+    }
+
+    #[classattr]
+    fn __match_args__() -> (
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+        &'static str,
+    ) {
+        (
+            "matched",
+            "important",
+            "redirect",
+            "redirect_type",
+            "redirect_resource_name",
+            "exception",
+            "filter",
+            "error",
+            "filter_source",
+            "exception_source",
+        )
+    }
+
+    /// The action to take for the request, following the engine's
+    /// precedence rules: an `important` match always blocks, exceptions
+    /// override other blocking filters, and a `redirect` only applies to
+    /// requests that would otherwise be blocked.
+    #[getter]
+    pub fn action(&self) -> BlockerAction {
+        // `important` matches are always `matched` as well.
+        if self.matched {
+            if self.redirect.is_some() {
+                BlockerAction::REDIRECT
+            } else {
+                BlockerAction::BLOCK
+            }
+        } else if self.exception.is_some() {
+            BlockerAction::EXCEPTED
+        } else {
+            BlockerAction::ALLOW
+        }
+    }
+
+    /// The MIME type of the resource the request is redirected to, if
+    /// `redirect_type` is `"resource"`.
+    #[getter]
+    pub fn redirect_mime_type(&self) -> Option<&str> {
+        self.redirect_resource().map(|(mime_type, _)| mime_type)
+    }
+
+    /// The decoded body of the resource the request is redirected to, if
+    /// `redirect_type` is `"resource"`.
+    #[getter]
+    pub fn redirect_bytes<'p>(&self, py: Python<'p>) -> Option<&'p PyBytes> {
+        let (_, data) = self.redirect_resource()?;
+        let bytes = base64::decode(data).ok()?;
+        Some(PyBytes::new(py, &bytes))
+    }
+
+    /// A `data:` URL holding the resource the request is redirected to, if
+    /// `redirect_type` is `"resource"`. Can be served as is in place of the
+    /// original request.
+    #[getter]
+    pub fn redirect_data_url(&self) -> Option<&str> {
+        self.redirect_resource()?;
+        self.redirect.as_deref()
+    }
+
+    /// Returns the fields of the result as a dictionary, in the same order as
+    /// the arguments of the constructor.
+    #[pyo3(text_signature = "($self)")]
+    pub fn to_dict<'p>(&self, py: Python<'p>) -> PyResult<&'p PyDict> {
+        let dict = PyDict::new(py);
+        dict.set_item("matched", self.matched)?;
+        dict.set_item("important", self.important)?;
+        dict.set_item("redirect", &self.redirect)?;
+        dict.set_item("redirect_type", &self.redirect_type)?;
+        dict.set_item("redirect_resource_name", &self.redirect_resource_name)?;
+        dict.set_item("exception", &self.exception)?;
+        dict.set_item("filter", &self.filter)?;
+        dict.set_item("error", &self.error)?;
+        dict.set_item("filter_source", &self.filter_source)?;
+        dict.set_item("exception_source", &self.exception_source)?;
+        Ok(dict)
+    }
+
+    fn __richcmp__(&self, other: &PyAny, op: CompareOp) -> PyObject {
+        let py = other.py();
+        let other = match other.extract::<PyRef<'_, Self>>() {
+            Ok(other) => other,
+            Err(_) => return py.NotImplemented(),
+        };
+        match op {
+            CompareOp::Eq => (*self == *other).into_py(py),
+            CompareOp::Ne => (*self != *other).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    fn __hash__(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn __reduce__(&self, py: Python<'_>) -> (PyObject, PyObject) {
+        let args = (
+            self.matched,
+            self.important,
+            self.redirect.clone(),
+            self.redirect_type.clone(),
+            self.redirect_resource_name.clone(),
+            self.exception.clone(),
+            self.filter.clone(),
+            self.error.clone(),
+            self.filter_source.clone(),
+            self.exception_source.clone(),
+        );
+        (py.get_type::<Self>().into_py(py), args.into_py(py))
+    }
+
     fn __repr__(&self) -> PyResult<String> {
         Ok(format!(
-            "BlockerResult(matched={}, important={}, redirect={}, exception={}, filter={}, error={})",
+            "BlockerResult(matched={}, important={}, redirect={}, redirect_type={}, redirect_resource_name={}, exception={}, filter={}, error={}, filter_source={}, exception_source={})",
             self.matched.diy_python_repr(),
             self.important.diy_python_repr(),
             self.redirect.diy_python_repr(),
+            self.redirect_type.diy_python_repr(),
+            self.redirect_resource_name.diy_python_repr(),
             self.exception.diy_python_repr(),
             self.filter.diy_python_repr(),
             self.error.diy_python_repr(),
+            self.filter_source.diy_python_repr(),
+            self.exception_source.diy_python_repr(),
         ))
     }
 }
@@ -200,6 +516,14 @@ create_exception!(adblock, DeserializationError, BlockerException);
 create_exception!(adblock, OptimizedFilterExistence, BlockerException);
 create_exception!(adblock, BadFilterAddUnsupported, BlockerException);
 create_exception!(adblock, FilterExists, BlockerException);
+create_exception!(adblock, InvalidRequestError, AdblockException);
+create_exception!(adblock, InvalidRequestTypeError, AdblockException);
+create_exception!(adblock, InvalidOptionError, AdblockException);
+create_exception!(adblock, FilterParseError, AdblockException);
+create_exception!(adblock, ConversionError, AdblockException);
+create_exception!(adblock, InvalidUrlError, InvalidRequestError);
+create_exception!(adblock, InvalidRequestUrlError, InvalidUrlError);
+create_exception!(adblock, InvalidSourceUrlError, InvalidUrlError);
 
 impl From<BlockerError> for PyErr {
     fn from(err: BlockerError) -> Self {
@@ -226,39 +550,501 @@ impl From<RustBlockerError> for BlockerError {
     }
 }
 
-fn filter_format_from_string(filter_format: &str) -> PyResult<FilterFormat> {
-    match filter_format {
-        "standard" => Ok(FilterFormat::Standard),
-        "hosts" => Ok(FilterFormat::Hosts),
-        _ => Err(PyErr::new::<AdblockException, _>(
-            "Invalid FilterFormat value",
+/// The reason a URL given to one of the request checking methods could not be
+/// parsed.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum UrlError {
+    Empty,
+    MissingScheme,
+    UnsupportedScheme,
+    MissingHost,
+    InvalidHost,
+}
+
+impl UrlError {
+    /// Works out why the request parser rejected `url`. Only meaningful for
+    /// URLs that `parse_url` failed on.
+    fn diagnose(url: &str) -> Self {
+        let url = url.trim_matches(|c: char| c <= ' ');
+        if url.is_empty() {
+            return Self::Empty;
+        }
+        let (scheme, rest) = match url.split_once(':') {
+            Some((scheme, rest))
+                if scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)) =>
+            {
+                (scheme.to_ascii_lowercase(), rest)
+            }
+            _ => return Self::MissingScheme,
+        };
+        let authority = match scheme.as_str() {
+            "file" => return Self::UnsupportedScheme,
+            "http" | "https" | "ws" | "wss" | "ftp" | "gopher" => {
+                rest.trim_start_matches(['/', '\\'])
+            }
+            _ => match rest.strip_prefix("//") {
+                Some(authority) => authority,
+                None => return Self::MissingHost,
+            },
+        };
+        let authority = authority
+            .split(['/', '?', '#', '\\'])
+            .next()
+            .unwrap_or_default();
+        let host = authority.rsplit('@').next().unwrap_or_default();
+        let host = if host.starts_with('[') {
+            host
+        } else {
+            host.split(':').next().unwrap_or_default()
+        };
+        if host.is_empty() {
+            Self::MissingHost
+        } else {
+            Self::InvalidHost
+        }
+    }
+
+    /// A short, stable identifier for the reason, exposed to Python as the
+    /// `reason` attribute of `InvalidUrlError`.
+    fn reason(self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::MissingScheme => "missing_scheme",
+            Self::UnsupportedScheme => "unsupported_scheme",
+            Self::MissingHost => "missing_host",
+            Self::InvalidHost => "invalid_host",
+        }
+    }
+}
+
+impl Error for UrlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
+}
+
+impl Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Empty => "empty URL",
+                Self::MissingScheme => "missing or invalid scheme",
+                Self::UnsupportedScheme => "unsupported scheme",
+                Self::MissingHost => "missing host",
+                Self::InvalidHost => "invalid host",
+            }
+        )
+    }
+}
+
+/// Which of the URLs of a request could not be parsed.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum UrlArgument {
+    Url,
+    SourceUrl,
+}
+
+/// Builds the `InvalidUrlError` subclass for a URL that could not be parsed,
+/// with `url` and `reason` attributes describing the failure.
+fn invalid_url_error(py: Python<'_>, argument: UrlArgument, url: &str) -> PyErr {
+    let reason = UrlError::diagnose(url);
+    let err = match argument {
+        UrlArgument::Url => InvalidRequestUrlError::new_err(format!(
+            "Invalid request URL {}: {}",
+            url.to_string().diy_python_repr(),
+            reason
+        )),
+        UrlArgument::SourceUrl => InvalidSourceUrlError::new_err(format!(
+            "Invalid source URL {}: {}",
+            url.to_string().diy_python_repr(),
+            reason
         )),
+    };
+    let value = err.value(py);
+    if let Err(e) = value
+        .setattr("url", url)
+        .and_then(|_| value.setattr("reason", reason.reason()))
+    {
+        return e;
+    }
+    err
+}
+
+/// Checks that the request parser can make sense of the given URLs. An empty
+/// `source_url` is accepted, since it means the request has no source.
+fn check_request_urls(py: Python<'_>, url: &str, source_url: &str) -> PyResult<()> {
+    if RustRequest::from_urls(url, source_url, "").is_err() {
+        return Err(invalid_url_error(py, UrlArgument::Url, url));
+    }
+    // `Request::from_urls` ignores a source URL it can't parse.
+    if !source_url.is_empty() && parse_url(source_url).is_none() {
+        return Err(invalid_url_error(py, UrlArgument::SourceUrl, source_url));
     }
+    Ok(())
 }
 
-fn rule_types_from_string(rule_types: &str) -> PyResult<RuleTypes> {
-    match rule_types {
-        "all" => Ok(RuleTypes::All),
-        "networkonly" => Ok(RuleTypes::NetworkOnly),
-        "cosmeticonly" => Ok(RuleTypes::CosmeticOnly),
-        _ => Err(PyErr::new::<AdblockException, _>("Invalid RuleTypes value")),
+/// Parses a single filter rule, returning a `NetworkFilter` or a
+/// `CosmeticFilter` describing it.
+///
+/// Raises `FilterParseError` if the rule can't be parsed, or if it isn't a
+/// filter rule at all, such as a comment. The message gives the reason, using
+/// the same kinds as `RejectedFilter.kind`.
+#[pyfunction(include_redirect_urls = "false")]
+#[pyo3(text_signature = "(text, include_redirect_urls)")]
+pub fn parse_filter(py: Python<'_>, text: &str, include_redirect_urls: bool) -> PyResult<PyObject> {
+    let opts = ParseOptions {
+        include_redirect_urls,
+        ..ParseOptions::default()
+    };
+    match parse_rust_filter(text, true, opts) {
+        Ok(ParsedFilter::Network(filter)) => Ok(NetworkFilter::new(filter, text).into_py(py)),
+        Ok(ParsedFilter::Cosmetic(filter)) => Ok(CosmeticFilter::new(filter, text).into_py(py)),
+        Err(error) => Err(filter_parse_error(py, text, &error)),
     }
 }
 
+/// Returns the canonical form of a single filter rule. Rules with the same
+/// meaning but written differently, such as `||example.com^$3p,xhr` and
+/// `||example.com^$xmlhttprequest,third-party`, have the same canonical form,
+/// which parses into an equivalent filter.
+///
+/// For network filters, option aliases are replaced by their full names,
+/// options are put in a fixed order, `$domain` values are sorted and the
+/// pattern is lowercased, unless it is a regular expression or the rule has
+/// `$match-case`. For cosmetic filters, hostnames are sorted and the
+/// arguments of scriptlets are separated by `", "`.
+///
+/// Raises `FilterParseError` like `parse_filter` if the rule can't be parsed.
+#[pyfunction(include_redirect_urls = "false")]
+#[pyo3(text_signature = "(text, include_redirect_urls)")]
+pub fn normalize_filter(
+    py: Python<'_>,
+    text: &str,
+    include_redirect_urls: bool,
+) -> PyResult<String> {
+    let opts = ParseOptions {
+        include_redirect_urls,
+        ..ParseOptions::default()
+    };
+    match parse_rust_filter(text, false, opts) {
+        Ok(ParsedFilter::Network(filter)) => Ok(filter::canonical_network(&filter, text)),
+        Ok(ParsedFilter::Cosmetic(filter)) => Ok(filter::canonical_cosmetic(&filter, text)),
+        Err(error) => Err(filter_parse_error(py, text, &error)),
+    }
+}
+
+/// Builds a `FilterParseError`, with the `text` of the rule, its
+/// `filter_type` and the `kind` of error as attributes. Both are `None` if the
+/// text isn't a filter rule.
+fn filter_parse_error(py: Python<'_>, text: &str, error: &RustFilterParseError) -> PyErr {
+    let rejection = report::parse_error_kind(error);
+    let err = FilterParseError::new_err(match rejection {
+        Some((filter_type, kind)) => format!(
+            "Invalid {} filter {}: {}",
+            filter_type,
+            text.diy_python_repr(),
+            kind
+        ),
+        None => format!("{} is not a filter rule", text.diy_python_repr()),
+    });
+    let value = err.value(py);
+    if let Err(e) = value
+        .setattr("text", text)
+        .and_then(|_| value.setattr("filter_type", rejection.map(|(filter_type, _)| filter_type)))
+        .and_then(|_| value.setattr("kind", rejection.map(|(_, kind)| kind)))
+    {
+        return e;
+    }
+    err
+}
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const GZIP_MAGIC: &[u8] = b"\x1F\x8B";
+/// Bumped whenever the format of `FilterSet.serialize` changes.
+const FILTER_SET_FORMAT_VERSION: u8 = 2;
+
 /// Manages a set of rules to be added to an Engine.
 ///
 /// To be able to efficiently handle special options like $badfilter, and to
 /// allow optimizations, all rules must be available when the Engine is first
 /// created. FilterSet allows assembling a compound list from multiple
 /// different sources before compiling the rules into an Engine.
-#[pyclass]
+#[pyclass(module = "adblock")]
 #[pyo3(text_signature = "($self, debug)")]
 #[derive(Clone)]
 pub struct FilterSet {
-    filter_set: RustFilterSet,
+    /// The upstream `FilterSet` doesn't give access to its filters, so they
+    /// are kept here instead. The `Engine` needs the network filters to
+    /// explain its decisions.
+    network_filters: Vec<RustNetworkFilter>,
+    /// The cosmetic filters, parsed once when they are added. Each one keeps
+    /// its text as its `raw_line`, even without debugging, see
+    /// `cosmetic_text`.
+    cosmetic_filters: Vec<RustCosmeticFilter>,
+    /// Where each network filter came from, in the same order as
+    /// `network_filters`.
+    network_origins: Vec<FilterOrigin>,
+    /// Where each cosmetic filter came from, in the same order as
+    /// `cosmetic_filters`.
+    cosmetic_origins: Vec<FilterOrigin>,
+    /// Counted as filters are added, see `push_network` and `push_cosmetic`.
+    stats: FilterSetStats,
     debug: bool,
 }
 
+/// Returns the text of a cosmetic filter kept by a `FilterSet`.
+fn cosmetic_text(filter: &RustCosmeticFilter) -> &str {
+    filter.raw_line.as_deref().map_or("", String::as_str)
+}
+
+/// Where a filter in a `FilterSet` came from.
+#[derive(Clone, Serialize, Deserialize)]
+struct FilterOrigin {
+    /// The `source` given when adding the filter.
+    source: Option<String>,
+    /// The name of the list the filter was included from with an
+    /// `!#include` directive.
+    include: Option<String>,
+    /// The 1-based line number of the filter, as in `RejectedFilter`.
+    line_number: usize,
+}
+
+impl FilterSet {
+    fn push_network(&mut self, filter: RustNetworkFilter, origin: FilterOrigin) {
+        self.stats.network_filters += 1;
+        if filter.is_exception() {
+            self.stats.exception_filters += 1;
+        }
+        if filter.is_redirect() {
+            self.stats.redirect_filters += 1;
+        }
+        self.network_filters.push(filter);
+        self.network_origins.push(origin);
+    }
+
+    fn push_cosmetic(&mut self, filter: RustCosmeticFilter, origin: FilterOrigin) {
+        self.stats.cosmetic_filters += 1;
+        if filter.mask.contains(CosmeticFilterMask::SCRIPT_INJECT) {
+            self.stats.scriptlet_filters += 1;
+        }
+        self.cosmetic_filters.push(filter);
+        self.cosmetic_origins.push(origin);
+    }
+
+    fn add_lines<'a>(
+        &mut self,
+        lines: impl IntoIterator<Item = &'a str> + Clone,
+        mut opts: ListOptions,
+        source: Option<&str>,
+    ) -> ParseReport {
+        opts.format = opts.format.resolve(lines.clone());
+        let mut report = ParseReport::default();
+        for (index, line) in lines.into_iter().enumerate() {
+            self.add_line(&mut report, None, index + 1, line, opts, source);
+        }
+        report
+    }
+
+    /// Adds a single line to this FilterSet. `include` is the name of the
+    /// list the line was included from with an `!#include` directive, and
+    /// `source` the identifier given when adding the list. The format in
+    /// `opts` must not be `Auto`.
+    fn add_line(
+        &mut self,
+        report: &mut ParseReport,
+        include: Option<&str>,
+        line_number: usize,
+        line: &str,
+        opts: ListOptions,
+        source: Option<&str>,
+    ) {
+        if include.is_none() {
+            report.metadata.try_add(line);
+        }
+        let origin = FilterOrigin {
+            source: source.map(str::to_string),
+            include: include.map(str::to_string),
+            line_number,
+        };
+        let mut rejections = Vec::new();
+        match opts.format.convert(line) {
+            Converted::Rule(format, rule) => {
+                rejections.extend(self.add_rule(
+                    report,
+                    &rule,
+                    opts.parse_options(format),
+                    opts.canonicalize,
+                    &origin,
+                ));
+            }
+            Converted::Hostnames(hostnames) => {
+                let parse_options = opts.parse_options(RustFilterFormat::Hosts);
+                for hostname in hostnames {
+                    rejections.extend(self.add_rule(
+                        report,
+                        hostname,
+                        parse_options,
+                        opts.canonicalize,
+                        &origin,
+                    ));
+                }
+            }
+            Converted::Ignored => report.ignored += 1,
+            Converted::Rejected(kind) => rejections.push(("network", kind)),
+        }
+        for (filter_type, kind) in rejections {
+            report.rejected.push(RejectedFilter {
+                line_number,
+                include: include.map(str::to_string),
+                text: line.to_string(),
+                filter_type,
+                kind,
+            });
+        }
+    }
+
+    /// Parses and adds a single filter rule. Returns the type of filter and
+    /// kind of error if it was rejected, see `report::parse_error_kind`. With
+    /// `canonicalize`, the canonical form of the rule is kept instead of its
+    /// text.
+    fn add_rule(
+        &mut self,
+        report: &mut ParseReport,
+        rule: &str,
+        opts: ParseOptions,
+        canonicalize: bool,
+        origin: &FilterOrigin,
+    ) -> Option<(&'static str, &'static str)> {
+        match parse_rust_filter(rule, self.debug, opts) {
+            Ok(ParsedFilter::Network(mut filter)) => {
+                if canonicalize && self.debug {
+                    let canonical = match (&opts.format, &filter.hostname) {
+                        // Hosts entries are parsed like `||hostname^`.
+                        (RustFilterFormat::Hosts, Some(hostname)) => format!("||{}^", hostname),
+                        _ => filter::canonical_network(&filter, rule),
+                    };
+                    filter.raw_line = Some(Box::new(canonical));
+                }
+                self.push_network(filter, origin.clone());
+                report.network_filters += 1;
+            }
+            Ok(ParsedFilter::Cosmetic(mut filter)) => {
+                let text = if canonicalize {
+                    filter::canonical_cosmetic(&filter, rule)
+                } else {
+                    rule.trim().to_string()
+                };
+                filter.raw_line = Some(Box::new(text));
+                self.push_cosmetic(filter, origin.clone());
+                report.cosmetic_filters += 1;
+            }
+            Err(error) => match report::parse_error_kind(&error) {
+                Some(rejection) => return Some(rejection),
+                None => report.ignored += 1,
+            },
+        }
+        None
+    }
+
+    /// Returns a new FilterSet holding the filters of both `self` and `other`,
+    /// without duplicates. See `merge` for how `debug` is handled.
+    fn union(&self, other: &FilterSet) -> FilterSet {
+        let mut union = FilterSet::new(self.debug && other.debug);
+
+        // `get_id` is what the blocker uses to identify filters, but it
+        // leaves out the redirect resource and the tag. Duplicates keep the
+        // origin of the first copy.
+        let mut seen_network = HashSet::new();
+        let network = self
+            .network_filters
+            .iter()
+            .zip(&self.network_origins)
+            .chain(other.network_filters.iter().zip(&other.network_origins));
+        for (filter, origin) in network {
+            if seen_network.insert((filter.get_id(), filter.redirect.clone(), filter.tag.clone())) {
+                let mut filter = filter.clone();
+                if !union.debug {
+                    filter.raw_line = None;
+                }
+                union.push_network(filter, origin.clone());
+            }
+        }
+
+        let mut seen_cosmetic = HashSet::new();
+        let cosmetic = self
+            .cosmetic_filters
+            .iter()
+            .zip(&self.cosmetic_origins)
+            .chain(other.cosmetic_filters.iter().zip(&other.cosmetic_origins));
+        for (filter, origin) in cosmetic {
+            if seen_cosmetic.insert(cosmetic_text(filter)) {
+                union.push_cosmetic(filter.clone(), origin.clone());
+            }
+        }
+        union
+    }
+
+    /// Builds an upstream `FilterSet` holding the cosmetic filters. The
+    /// upstream `Engine` can only be given cosmetic filters through a
+    /// `FilterSet`, which only takes them as text.
+    fn cosmetic_filter_set(&self) -> RustFilterSet {
+        let mut filter_set = RustFilterSet::new(self.debug);
+        let rules: Vec<String> = self
+            .cosmetic_filters
+            .iter()
+            .map(|filter| cosmetic_text(filter).to_string())
+            .collect();
+        // These filters have been parsed successfully before, so the format
+        // and rule types don't matter.
+        filter_set.add_filters(&rules, ParseOptions::default());
+        filter_set
+    }
+
+    /// Adds the lines read from `reader`, one at a time. Lines that aren't
+    /// valid UTF-8 are decoded lossily.
+    fn add_reader(
+        &mut self,
+        mut reader: impl BufRead,
+        mut opts: ListOptions,
+        source: Option<&str>,
+    ) -> io::Result<ParseReport> {
+        if opts.format == FilterFormat::AUTO {
+            // Detect the format from the lines that have already been read.
+            let start = String::from_utf8_lossy(reader.fill_buf()?);
+            opts.format = formats::detect(start.lines());
+        }
+        let mut report = ParseReport::default();
+        let mut buffer = Vec::new();
+        let mut line_number = 0;
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                return Ok(report);
+            }
+            line_number += 1;
+            let mut line = buffer.as_slice();
+            if line_number == 1 {
+                line = line.strip_prefix(UTF8_BOM).unwrap_or(line);
+            }
+            let line = line.strip_suffix(b"\n").unwrap_or(line);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            self.add_line(
+                &mut report,
+                None,
+                line_number,
+                &String::from_utf8_lossy(line),
+                opts,
+                source,
+            );
+        }
+    }
+}
+
 #[pymethods]
 impl FilterSet {
     /// Creates a new `FilterSet`. The `debug` argument specifies whether or
@@ -269,80 +1055,475 @@ impl FilterSet {
     #[args(debug = false)]
     pub fn new(debug: bool) -> Self {
         Self {
-            filter_set: RustFilterSet::new(debug),
+            network_filters: Vec::new(),
+            cosmetic_filters: Vec::new(),
+            network_origins: Vec::new(),
+            cosmetic_origins: Vec::new(),
+            stats: FilterSetStats::default(),
             debug,
         }
     }
 
     /// Adds the contents of an entire filter list to this FilterSet. Filters
-    /// that cannot be parsed successfully are ignored.
+    /// that cannot be parsed successfully are ignored, and listed in the
+    /// returned `ParseReport`. The report also holds the metadata found in
+    /// the list's header comments, such as `! Title:` and `! Expires:`.
+    ///
+    /// The format is a `FilterFormat`, or the string value of one:
+    /// * `"standard"` - ABP/uBO-style filters.
+    /// * `"hosts"` - Hosts file entries, such as `0.0.0.0 example.com`.
+    /// * `"domains"` - One domain per line, such as `example.com`, or
+    ///   `*.example.com` for its subdomains only.
+    /// * `"dnsmasq"` - dnsmasq configuration, such as
+    ///   `address=/example.com/0.0.0.0` or `server=/example.com/`.
+    /// * `"adguard_dns"` - AdGuard Home DNS filters. Filters restricted to
+    ///   some clients or DNS record types, with `$client`, `$ctag` or
+    ///   `$dnstype`, are rejected with the `"dns_only_option"` kind, and
+    ///   filters with `$dnsrewrite` are ignored.
+    /// * `"auto"` - Any of the above, detected from the first rules of the
+    ///   list.
     ///
-    /// The format is a string containing either "standard" (ABP/uBO-style)
-    /// or "hosts".
-    #[pyo3(text_signature = "($self, filter_list, format, include_redirect_urls, rule_types)")]
+    /// Formats other than "standard" are converted into equivalent
+    /// `||example.com^` network filters.
+    ///
+    /// `rule_types` is a `RuleTypes`, or its string value, selecting whether
+    /// network filters, cosmetic filters or both are kept. Unknown formats
+    /// and rule types raise `InvalidOptionError`.
+    ///
+    /// If `env` or `include_resolver` is given, the `!#if`, `!#else`,
+    /// `!#endif` and `!#include` preprocessor directives are applied, instead
+    /// of being treated as comments:
+    /// * `env` - The names of the flags that are true in `!#if` conditions,
+    ///   such as `env_firefox`. Other names are false.
+    /// * `include_resolver` - Either a callable, which is given the name of
+    ///   an included list and returns its contents or `None`, or the path of
+    ///   a directory holding the included lists. Names in included lists are
+    ///   relative to them, so `b.txt` included from `sub/a.txt` is resolved
+    ///   as `sub/b.txt`.
+    ///
+    /// Lists that can't be resolved are listed in the report's
+    /// `unresolved_includes`, and lists including themselves, directly or
+    /// not, in its `include_cycles`.
+    ///
+    /// `source` is an identifier for the list, such as its name or URL. It is
+    /// kept with each of the list's filters, and reported by the `Engine` as
+    /// `BlockerResult.filter_source`, `BlockerResult.exception_source` and
+    /// `UrlSpecificResources.selector_sources`. The sources of network filters
+    /// are only reported if this FilterSet was created with `debug=True`.
+    ///
+    /// With `canonicalize=True`, filters are kept in their canonical form, as
+    /// returned by `normalize_filter`, instead of as written. This applies to
+    /// the text of cosmetic filters, and of network filters if this FilterSet
+    /// was created with `debug=True`.
+    #[pyo3(
+        text_signature = "($self, filter_list, format, include_redirect_urls, rule_types, env, include_resolver, source, canonicalize)"
+    )]
     #[args(
         filter_list,
-        format = "\"standard\"",
+        format = "FilterFormatArg::Enum(FilterFormat::STANDARD)",
         include_redirect_urls = "false",
-        rule_types = "\"all\""
+        rule_types = "RuleTypesArg::Enum(RuleTypes::ALL)",
+        env = "None",
+        include_resolver = "None",
+        source = "None",
+        canonicalize = "false"
     )]
+    #[allow(clippy::too_many_arguments)]
     pub fn add_filter_list(
         &mut self,
+        py: Python<'_>,
         filter_list: &str,
-        format: &str,
+        format: FilterFormatArg<'_>,
         include_redirect_urls: bool,
-        rule_types: &str,
-    ) -> PyResult<()> {
-        let filter_format = filter_format_from_string(format)?;
-        let rule_types = rule_types_from_string(rule_types)?;
-        self.filter_set.add_filter_list(
-            filter_list,
-            ParseOptions {
-                format: filter_format,
-                include_redirect_urls,
-                rule_types,
-            },
-        );
-        Ok(())
+        rule_types: RuleTypesArg<'_>,
+        env: Option<&PyAny>,
+        include_resolver: Option<IncludeResolver>,
+        source: Option<&str>,
+        canonicalize: bool,
+    ) -> PyResult<ParseReport> {
+        let filter_format = format.resolve()?;
+        let rule_types = rule_types.resolve()?;
+        let opts = ListOptions {
+            format: filter_format.resolve(filter_list.lines()),
+            include_redirect_urls,
+            rule_types,
+            canonicalize,
+        };
+        if env.is_none() && include_resolver.is_none() {
+            return Ok(self.add_lines(filter_list.lines(), opts, source));
+        }
+
+        let env = match env {
+            Some(env) => preprocess::env_from_python(env)?,
+            None => HashSet::new(),
+        };
+        let mut preprocessor = Preprocessor {
+            env: &env,
+            resolver: include_resolver.as_ref(),
+            opts,
+            source,
+            include_stack: Vec::new(),
+        };
+        let mut report = ParseReport::default();
+        preprocessor.add_filter_list(py, self, &mut report, filter_list, None)?;
+        Ok(report)
     }
 
     /// Adds a collection of filter rules to this FilterSet. Filters that
-    /// cannot be parsed successfully are ignored.
+    /// cannot be parsed successfully are ignored, and listed in the returned
+    /// `ParseReport`.
     ///
-    /// The format is a string containing either "standard" (ABP/uBO-style)
-    /// or "hosts".
-    #[pyo3(text_signature = "($self, filters, format, include_redirect_urls, rule_types)")]
+    /// The format is one of the formats accepted by `add_filter_list`, where
+    /// `"auto"` detects the format from the first filters given. `source`
+    /// identifies where the filters came from, and `canonicalize` keeps the
+    /// canonical form of the filters, see `add_filter_list`.
+    #[pyo3(
+        text_signature = "($self, filters, format, include_redirect_urls, rule_types, source, canonicalize)"
+    )]
     #[args(
         filters,
-        format = "\"standard\"",
+        format = "FilterFormatArg::Enum(FilterFormat::STANDARD)",
         include_redirect_urls = "false",
-        rule_types = "\"all\""
+        rule_types = "RuleTypesArg::Enum(RuleTypes::ALL)",
+        source = "None",
+        canonicalize = "false"
     )]
     pub fn add_filters(
         &mut self,
         filters: Vec<String>,
-        format: &str,
+        format: FilterFormatArg<'_>,
         include_redirect_urls: bool,
-        rule_types: &str,
-    ) -> PyResult<()> {
-        let filter_format = filter_format_from_string(format)?;
-        let rule_types = rule_types_from_string(rule_types)?;
-        self.filter_set.add_filters(
-            &filters,
-            ParseOptions {
+        rule_types: RuleTypesArg<'_>,
+        source: Option<&str>,
+        canonicalize: bool,
+    ) -> PyResult<ParseReport> {
+        let filter_format = format.resolve()?;
+        let rule_types = rule_types.resolve()?;
+        Ok(self.add_lines(
+            filters.iter().map(String::as_str),
+            ListOptions {
                 format: filter_format,
                 include_redirect_urls,
                 rule_types,
+                canonicalize,
             },
+            source,
+        ))
+    }
+
+    /// Adds the contents of the filter list stored at `path` to this
+    /// FilterSet, like `add_filter_list`. The file is read one line at a
+    /// time, so the whole list is never held in memory. Gzip compressed files
+    /// are decompressed transparently, and a leading UTF-8 byte order mark
+    /// and CRLF line endings are handled. With the `"auto"` format, the
+    /// format is detected from the start of the file.
+    #[pyo3(
+        text_signature = "($self, path, format, include_redirect_urls, rule_types, source, canonicalize)"
+    )]
+    #[args(
+        path,
+        format = "FilterFormatArg::Enum(FilterFormat::STANDARD)",
+        include_redirect_urls = "false",
+        rule_types = "RuleTypesArg::Enum(RuleTypes::ALL)",
+        source = "None",
+        canonicalize = "false"
+    )]
+    pub fn add_filter_list_from_file(
+        &mut self,
+        path: PathBuf,
+        format: FilterFormatArg<'_>,
+        include_redirect_urls: bool,
+        rule_types: RuleTypesArg<'_>,
+        source: Option<&str>,
+        canonicalize: bool,
+    ) -> PyResult<ParseReport> {
+        let filter_format = format.resolve()?;
+        let rule_types = rule_types.resolve()?;
+        let opts = ListOptions {
+            format: filter_format,
+            include_redirect_urls,
+            rule_types,
+            canonicalize,
+        };
+        let mut reader = BufReader::new(fs::File::open(path)?);
+        let report = if reader.fill_buf()?.starts_with(GZIP_MAGIC) {
+            self.add_reader(BufReader::new(MultiGzDecoder::new(reader)), opts, source)?
+        } else {
+            self.add_reader(reader, opts, source)?
+        };
+        Ok(report)
+    }
+
+    /// Adds the filters of `other` to this FilterSet, removing duplicate
+    /// filters. Filters are identified by what they match and their options,
+    /// rather than by their text.
+    ///
+    /// The merged FilterSet only has debugging enabled if both FilterSets
+    /// have it enabled. Merging a FilterSet without debugging into one with
+    /// it disables debugging, and the text of the filters is discarded.
+    #[pyo3(text_signature = "($self, other)")]
+    pub fn merge(slf: &PyCell<Self>, other: &PyCell<FilterSet>) {
+        // Borrow both sets immutably first, so a set can be merged with
+        // itself.
+        let union = slf.borrow().union(&other.borrow());
+        *slf.borrow_mut() = union;
+    }
+
+    /// Returns a copy of this FilterSet, which can be modified without
+    /// affecting the original.
+    #[pyo3(text_signature = "($self)")]
+    pub fn copy(&self) -> FilterSet {
+        self.clone()
+    }
+
+    /// Returns a new FilterSet holding the filters of both FilterSets, see
+    /// `merge`.
+    fn __or__(&self, other: PyRef<'_, FilterSet>) -> FilterSet {
+        self.union(&other)
+    }
+
+    /// Adds the filters of `other` to this FilterSet, see `merge`.
+    fn __ior__(slf: &PyCell<Self>, other: &PyCell<FilterSet>) {
+        Self::merge(slf, other);
+    }
+
+    fn __copy__(&self) -> FilterSet {
+        self.clone()
+    }
+
+    /// Serialize this FilterSet to bytes. They can then be deserialized using
+    /// `deserialize()` to get the same FilterSet again, without parsing the
+    /// filters again. FilterSets can also be pickled.
+    #[pyo3(text_signature = "($self)")]
+    pub fn serialize<'p>(&self, py: Python<'p>) -> PyResult<&'p PyBytes> {
+        let state = (
+            FILTER_SET_FORMAT_VERSION,
+            self.debug,
+            &self.network_filters,
+            &self.cosmetic_filters,
+            &self.network_origins,
+            &self.cosmetic_origins,
         );
+        let bytes = rmp_serde::to_vec(&state).map_err(|_| BlockerError::SerializationError)?;
+        Ok(PyBytes::new(py, &bytes))
+    }
+
+    /// Replace the contents of this FilterSet, including whether debugging
+    /// is enabled, with a FilterSet serialized with `serialize()`.
+    #[pyo3(text_signature = "($self, serialized)")]
+    pub fn deserialize(&mut self, serialized: &[u8]) -> PyResult<()> {
+        type State = (
+            u8,
+            bool,
+            Vec<RustNetworkFilter>,
+            Vec<RustCosmeticFilter>,
+            Vec<FilterOrigin>,
+            Vec<FilterOrigin>,
+        );
+        let (version, debug, network_filters, cosmetic_filters, network_origins, cosmetic_origins): State =
+            rmp_serde::from_slice(serialized).map_err(|_| BlockerError::DeserializationError)?;
+        if version != FILTER_SET_FORMAT_VERSION
+            || network_origins.len() != network_filters.len()
+            || cosmetic_origins.len() != cosmetic_filters.len()
+        {
+            return Err(BlockerError::DeserializationError.into());
+        }
+        let mut filter_set = Self::new(debug);
+        for (filter, origin) in network_filters.into_iter().zip(network_origins) {
+            filter_set.push_network(filter, origin);
+        }
+        for (filter, origin) in cosmetic_filters.into_iter().zip(cosmetic_origins) {
+            filter_set.push_cosmetic(filter, origin);
+        }
+        *self = filter_set;
         Ok(())
     }
 
+    fn __getstate__<'p>(&self, py: Python<'p>) -> PyResult<&'p PyBytes> {
+        self.serialize(py)
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        self.deserialize(state)
+    }
+
+    /// Checks the filters in this FilterSet for likely mistakes, returning a
+    /// list of `LintFinding`s in the order the filters were added. Finds
+    /// duplicate filters, filters cancelled by `$badfilter` rules, exceptions
+    /// no blocking filter matches, filters with overly broad patterns,
+    /// regexes the engine can't index, and invalid CSS selectors.
+    ///
+    /// Duplicate network filters are only told apart from semantic
+    /// duplicates, and findings only include the text of network filters,
+    /// if this FilterSet was created with `debug=True`.
+    #[pyo3(text_signature = "($self)")]
+    pub fn lint(&self) -> Vec<LintFinding> {
+        lint::lint(
+            &self.network_filters,
+            &self.network_origins,
+            &self.cosmetic_filters,
+            &self.cosmetic_origins,
+        )
+    }
+
+    /// Converts the filters in this FilterSet into Safari/WebKit content
+    /// blocker rules, returned as `ContentBlockingRules`. Its `json` is the
+    /// JSON array of rules, and `dropped` lists the filters without an
+    /// equivalent rule, such as `$redirect` filters, regular expressions and
+    /// scriptlets, along with the reason.
+    ///
+    /// Filters cancelled by a `$badfilter` rule are dropped as well. Raises
+    /// `ConversionError` if this FilterSet holds network filters but wasn't
+    /// created with `debug=True`, since their text is needed.
+    #[pyo3(text_signature = "($self)")]
+    pub fn to_content_blocking(&self) -> PyResult<ContentBlockingRules> {
+        export::to_content_blocking(
+            &self.network_filters,
+            &self.network_origins,
+            &self.cosmetic_filters,
+            &self.cosmetic_origins,
+        )
+    }
+
+    /// Converts the network filters in this FilterSet into Chromium
+    /// `declarativeNetRequest` rules, returned as
+    /// `DeclarativeNetRequestRules`. Its `json` is the JSON array of rules, to
+    /// be used as a static ruleset, and `dropped` lists the filters without an
+    /// equivalent rule, such as `$redirect-rule` and `$csp` filters, along
+    /// with the reason.
+    ///
+    /// Filters become `block`, `allow` and `redirect` rules, and `$document`
+    /// exceptions `allowAllRequests` rules. Filters with
+    /// `$csp=upgrade-insecure-requests` become `upgradeScheme` rules, which
+    /// upgrade the matching requests. Priorities keep the precedence of the
+    /// engine: exceptions override blocking filters, redirects override
+    /// blocking filters and `$important` filters override exceptions.
+    /// `$domain` options become `initiatorDomains` and
+    /// `excludedInitiatorDomains`.
+    ///
+    /// `$redirect` resources are expected in the extension at
+    /// `resource_path` followed by their name. At most `max_rules` rules are
+    /// returned, of which at most `max_regex_rules` use a `regexFilter`. The
+    /// defaults are Chrome's guaranteed number of static rules and its limit
+    /// of regex rules per ruleset, and `None` removes a limit. Exceptions are
+    /// kept before other filters when a limit is reached.
+    ///
+    /// Raises `ConversionError` if this FilterSet holds network filters but
+    /// wasn't created with `debug=True`, since their text is needed.
+    #[pyo3(text_signature = "($self, max_rules, max_regex_rules, resource_path)")]
+    #[args(max_rules = "30000", max_regex_rules = "1000", resource_path = "\"/\"")]
+    pub fn to_declarative_net_request(
+        &self,
+        max_rules: Option<usize>,
+        max_regex_rules: Option<usize>,
+        resource_path: &str,
+    ) -> PyResult<DeclarativeNetRequestRules> {
+        export::to_declarative_net_request(
+            &self.network_filters,
+            &self.network_origins,
+            max_rules,
+            max_regex_rules,
+            resource_path,
+        )
+    }
+
+    /// Returns the number of filters of each kind in this FilterSet, as a
+    /// `FilterSetStats`.
+    #[pyo3(text_signature = "($self)")]
+    pub fn stats(&self) -> FilterSetStats {
+        self.stats
+    }
+
+    /// The number of filters in this FilterSet.
+    fn __len__(&self) -> usize {
+        self.network_filters.len() + self.cosmetic_filters.len()
+    }
+
+    /// Iterates over the text of the filters in this FilterSet: first the
+    /// network filters, then the cosmetic filters, each in the order they
+    /// were added. Only available if debugging is enabled, since the text of
+    /// network filters is discarded otherwise.
+    fn __iter__(&self) -> PyResult<FilterIterator> {
+        if !self.debug {
+            return Err(AdblockException::new_err(
+                "Filters can only be iterated over if the FilterSet was created with debug=True",
+            ));
+        }
+        let network_rules = self
+            .network_filters
+            .iter()
+            .filter_map(|filter| filter.raw_line.as_ref().map(|line| line.to_string()));
+        let rules: Vec<String> = network_rules
+            .chain(
+                self.cosmetic_filters
+                    .iter()
+                    .map(|filter| cosmetic_text(filter).to_string()),
+            )
+            .collect();
+        Ok(FilterIterator {
+            rules: rules.into_iter(),
+        })
+    }
+
     fn __repr__(&self) -> PyResult<String> {
         Ok(format!("FilterSet(debug={})", self.debug.diy_python_repr()))
     }
 }
 
+/// The number of filters of each kind in a `FilterSet`, returned by
+/// `FilterSet.stats`.
+#[pyclass]
+#[derive(Default, Clone, Copy)]
+pub struct FilterSetStats {
+    /// The number of network filters, including exceptions and redirects.
+    #[pyo3(get)]
+    pub network_filters: usize,
+    /// The number of network filters that are exceptions (`@@`).
+    #[pyo3(get)]
+    pub exception_filters: usize,
+    /// The number of network filters with a `$redirect`, `$redirect-rule` or
+    /// `$redirect-url` option.
+    #[pyo3(get)]
+    pub redirect_filters: usize,
+    /// The number of cosmetic filters, including scriptlet injections.
+    #[pyo3(get)]
+    pub cosmetic_filters: usize,
+    /// The number of cosmetic filters injecting a scriptlet (`##+js(...)`).
+    #[pyo3(get)]
+    pub scriptlet_filters: usize,
+}
+
+#[pymethods]
+impl FilterSetStats {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "FilterSetStats(network_filters={}, exception_filters={}, redirect_filters={}, cosmetic_filters={}, scriptlet_filters={})",
+            self.network_filters,
+            self.exception_filters,
+            self.redirect_filters,
+            self.cosmetic_filters,
+            self.scriptlet_filters,
+        ))
+    }
+}
+
+/// An iterator over the text of the filters in a `FilterSet`.
+#[pyclass]
+pub struct FilterIterator {
+    rules: std::vec::IntoIter<String>,
+}
+
+#[pymethods]
+impl FilterIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<String> {
+        slf.rules.next()
+    }
+}
+
 /// Contains cosmetic filter information intended to be injected into a
 /// particular hostname.
 #[pyclass]
@@ -370,6 +1551,12 @@ pub struct UrlSpecificResources {
     /// query for additional generic rules using hidden_class_id_selectors.
     #[pyo3(get)]
     pub generichide: bool,
+    /// The `source` of the lists holding the cosmetic filters for each of
+    /// the selectors in `hide_selectors`, `style_selectors` and `exceptions`,
+    /// see `FilterSet.add_filter_list`. Selectors whose filters were all
+    /// added without a source are left out.
+    #[pyo3(get)]
+    pub selector_sources: HashMap<String, Vec<String>>,
 }
 
 impl From<RustUrlSpecificResources> for UrlSpecificResources {
@@ -380,6 +1567,7 @@ impl From<RustUrlSpecificResources> for UrlSpecificResources {
             exceptions: r.exceptions,
             injected_script: r.injected_script,
             generichide: r.generichide,
+            selector_sources: HashMap::new(),
         }
     }
 }
@@ -398,13 +1586,254 @@ impl UrlSpecificResources {
     }
 }
 
+/// A network request, parsed once so that it can be checked any number of
+/// times, against any number of engines, using `Engine.check`.
+#[pyclass]
+#[pyo3(text_signature = "($self, url, source_url, request_type, strict)")]
+#[derive(Clone)]
+pub struct Request {
+    request: RustRequest,
+    /// The domain (eTLD+1) of the request's hostname.
+    #[pyo3(get)]
+    pub domain: String,
+    /// The hostname of the URL from where the request is made. Empty if the
+    /// source URL could not be parsed.
+    #[pyo3(get)]
+    pub source_hostname: String,
+    /// The domain (eTLD+1) of the source hostname.
+    #[pyo3(get)]
+    pub source_domain: String,
+}
+
+#[pymethods]
+impl Request {
+    /// Parses a new request. Raises `InvalidRequestUrlError` if `url` cannot
+    /// be parsed.
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the request
+    /// * `source_url` - The URL from where the request is made
+    /// * `request_type` - The resource type that the request points to, as a
+    ///   `RequestType` or a string
+    /// * `strict` - Raise `InvalidRequestTypeError` for unknown request type
+    ///   strings, instead of treating them as `"other"`, and
+    ///   `InvalidSourceUrlError` for a source URL that cannot be parsed,
+    ///   instead of treating the request as having no source
+    #[new]
+    #[args(url, source_url, request_type, strict = "false")]
+    pub fn new(
+        py: Python<'_>,
+        url: &str,
+        source_url: &str,
+        request_type: RequestTypeArg<'_>,
+        strict: bool,
+    ) -> PyResult<Self> {
+        let request_type = request_type.resolve(strict)?;
+        if strict {
+            check_request_urls(py, url, source_url)?;
+        }
+        let request = RustRequest::from_urls(url, source_url, request_type)
+            .map_err(|_| invalid_url_error(py, UrlArgument::Url, url))?;
+        let domain = parse_url(url)
+            .map(|parsed| parsed.domain().to_string())
+            .unwrap_or_default();
+        let (source_hostname, source_domain) = parse_url(source_url)
+            .map(|parsed| (parsed.hostname().to_string(), parsed.domain().to_string()))
+            .unwrap_or_default();
+        Ok(Self {
+            request,
+            domain,
+            source_hostname,
+            source_domain,
+        })
+    }
+
+    /// The normalized URL of the request.
+    #[getter]
+    pub fn url(&self) -> &str {
+        &self.request.url
+    }
+
+    /// The hostname of the request's URL.
+    #[getter]
+    pub fn hostname(&self) -> &str {
+        &self.request.hostname
+    }
+
+    /// The resource type the request was classified as.
+    #[getter]
+    pub fn request_type(&self) -> &'static str {
+        request_type_name(&self.request.request_type)
+    }
+
+    /// Whether the request is made to a third party. `None` if the source
+    /// URL has no domain to compare against.
+    #[getter]
+    pub fn third_party(&self) -> Option<bool> {
+        self.request.is_third_party
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "Request(url={}, source_hostname={}, request_type={})",
+            self.request.url.diy_python_repr(),
+            self.source_hostname.diy_python_repr(),
+            request_type_name(&self.request.request_type)
+                .to_string()
+                .diy_python_repr(),
+        ))
+    }
+}
+
+fn request_type_name(request_type: &RustRequestType) -> &'static str {
+    match request_type {
+        RustRequestType::Beacon => "beacon",
+        RustRequestType::Csp => "csp_report",
+        RustRequestType::Document => "document",
+        RustRequestType::Dtd => "xml_dtd",
+        RustRequestType::Fetch => "fetch",
+        RustRequestType::Font => "font",
+        RustRequestType::Image => "image",
+        RustRequestType::Media => "media",
+        RustRequestType::Object => "object",
+        RustRequestType::Other => "other",
+        RustRequestType::Ping => "ping",
+        RustRequestType::Script => "script",
+        RustRequestType::Stylesheet => "stylesheet",
+        RustRequestType::Subdocument => "subdocument",
+        RustRequestType::Websocket => "websocket",
+        RustRequestType::Xlst => "xslt",
+        RustRequestType::Xmlhttprequest => "xmlhttprequest",
+    }
+}
+
+/// The resource types understood by the request parser. Anywhere a request
+/// type is expected, either a `RequestType` or its string value can be given.
+#[pyclass]
+#[allow(non_camel_case_types)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum RequestType {
+    BEACON,
+    CSP_REPORT,
+    DOCUMENT,
+    FONT,
+    IMAGE,
+    MEDIA,
+    OBJECT,
+    OTHER,
+    PING,
+    SCRIPT,
+    SPECULATIVE,
+    STYLESHEET,
+    SUBDOCUMENT,
+    WEB_MANIFEST,
+    WEBSOCKET,
+    XBL,
+    XML_DTD,
+    XMLHTTPREQUEST,
+    XSLT,
+}
+
+impl RequestType {
+    /// The string passed on to the request parser for this type.
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::BEACON => "beacon",
+            Self::CSP_REPORT => "csp_report",
+            Self::DOCUMENT => "document",
+            Self::FONT => "font",
+            Self::IMAGE => "image",
+            Self::MEDIA => "media",
+            Self::OBJECT => "object",
+            Self::OTHER => "other",
+            Self::PING => "ping",
+            Self::SCRIPT => "script",
+            Self::SPECULATIVE => "speculative",
+            Self::STYLESHEET => "stylesheet",
+            Self::SUBDOCUMENT => "subdocument",
+            Self::WEB_MANIFEST => "web_manifest",
+            Self::WEBSOCKET => "websocket",
+            Self::XBL => "xbl",
+            Self::XML_DTD => "xml_dtd",
+            Self::XMLHTTPREQUEST => "xmlhttprequest",
+            Self::XSLT => "xslt",
+        }
+    }
+
+    /// Mirrors the strings accepted by the upstream request parser, including
+    /// its aliases.
+    fn from_str(request_type: &str) -> Option<Self> {
+        match request_type {
+            "beacon" => Some(Self::BEACON),
+            "csp_report" => Some(Self::CSP_REPORT),
+            "document" | "main_frame" => Some(Self::DOCUMENT),
+            "font" => Some(Self::FONT),
+            "image" | "imageset" => Some(Self::IMAGE),
+            "media" => Some(Self::MEDIA),
+            "object" | "object_subrequest" => Some(Self::OBJECT),
+            "other" => Some(Self::OTHER),
+            "ping" => Some(Self::PING),
+            "script" => Some(Self::SCRIPT),
+            "speculative" => Some(Self::SPECULATIVE),
+            "stylesheet" => Some(Self::STYLESHEET),
+            "sub_frame" | "subdocument" => Some(Self::SUBDOCUMENT),
+            "web_manifest" => Some(Self::WEB_MANIFEST),
+            "websocket" => Some(Self::WEBSOCKET),
+            "xbl" => Some(Self::XBL),
+            "xml_dtd" => Some(Self::XML_DTD),
+            "xhr" | "xmlhttprequest" => Some(Self::XMLHTTPREQUEST),
+            "xslt" => Some(Self::XSLT),
+            _ => None,
+        }
+    }
+}
+
+#[pymethods]
+impl RequestType {
+    /// The string value of this request type.
+    #[getter]
+    pub fn value(&self) -> &'static str {
+        self.as_str()
+    }
+}
+
+/// A request type argument, given either as a `RequestType` or as a string.
+#[derive(FromPyObject)]
+pub enum RequestTypeArg<'a> {
+    #[pyo3(transparent, annotation = "RequestType")]
+    Enum(RequestType),
+    #[pyo3(transparent, annotation = "str")]
+    Str(&'a str),
+}
+
+impl<'a> RequestTypeArg<'a> {
+    /// Returns the string to pass on to the request parser. In `strict` mode,
+    /// strings the parser doesn't understand raise `InvalidRequestTypeError`
+    /// instead of silently falling back to `"other"`.
+    fn resolve(&self, strict: bool) -> PyResult<&'a str> {
+        match *self {
+            Self::Enum(request_type) => Ok(request_type.as_str()),
+            Self::Str(request_type) => {
+                if strict && RequestType::from_str(request_type).is_none() {
+                    Err(InvalidRequestTypeError::new_err(format!(
+                        "Invalid request type {}",
+                        request_type.to_string().diy_python_repr()
+                    )))
+                } else {
+                    Ok(request_type)
+                }
+            }
+        }
+    }
+}
+
 /// The main object featured in this library. This object holds the adblocker's
 /// state, and can be queried to see if a given request should be blocked or
 /// not.
 ///
 /// # Request types
 /// A few of `Engine`'s methods have a field specifying a "resource type",
-/// valid examples are:
+/// given either as a `RequestType` or as a string. Valid examples are:
 /// * `beacon`
 /// * `csp_report`
 /// * `document`
@@ -416,22 +1845,354 @@ impl UrlSpecificResources {
 /// * and et cetera...
 /// See the [Mozilla Web Documentation][1] for more info.
 ///
+/// Unknown strings are treated as `other`. Passing `strict=True` to these
+/// methods raises `InvalidRequestTypeError` for them instead.
+///
+/// # URL errors
+/// By default, `check_network_urls` signals URLs it cannot parse through the
+/// `error` field of the returned `BlockerResult`, and ignores a source URL it
+/// cannot parse. With `strict=True`, it raises `InvalidRequestUrlError` or
+/// `InvalidSourceUrlError` instead. Their `reason` attribute tells why the
+/// URL was rejected: `"empty"`, `"missing_scheme"`, `"unsupported_scheme"`,
+/// `"missing_host"` or `"invalid_host"`.
+///
+/// The `*_with_hostnames*` methods take the hostnames as given, without
+/// parsing or validating them, so their `strict` argument only applies to
+/// the request type.
+///
 /// [1]: https://developer.mozilla.org/en-US/docs/Mozilla/Add-ons/WebExtensions/API/webRequest/ResourceType
+///
+/// # Keeping filters
+/// `explain` and `check_network_urls_all_matches` need the network filters
+/// the engine was created from, which the blocker doesn't keep. Since that
+/// takes a second copy of every network filter, they are only kept when the
+/// engine is created with `keep_filters=True`. They are not serialized, so
+/// these methods are also unavailable after `deserialize`.
+///
+/// # Threading
+/// Methods that only query the engine release the GIL while the matching is
+/// done, so several Python threads can query a single engine at the same
+/// time. Methods that modify the engine wait until all running queries have
+/// finished.
 #[pyclass]
-#[pyo3(text_signature = "($self, filter_set, optimize)")]
+#[pyo3(text_signature = "($self, filter_set, optimize, keep_filters)")]
 pub struct Engine {
-    engine: RustEngine,
+    state: RwLock<EngineState>,
     optimize: bool,
+    keep_filters: bool,
+}
+
+struct EngineState {
+    engine: RustEngine,
+    /// The network filters the engine was created from, used by `explain`.
+    /// Only kept with `keep_filters=True`, and `None` once another engine has
+    /// been deserialized into this one.
+    filter_lists: Option<FilterLists>,
+    /// The filters with a redirect option, which the blocker picks the
+    /// resource it redirects to from. Not serialized, so empty once another
+    /// engine has been deserialized into this one.
+    redirect_filters: Vec<RustNetworkFilter>,
+    /// What the upstream engine doesn't keep about the filters.
+    extras: EngineExtras,
+}
+
+/// The sources of an engine's filters, which the upstream engine doesn't
+/// keep. Serialized along with it, see `EXTRAS_MAGIC`.
+#[derive(Default, Serialize, Deserialize)]
+struct EngineExtras {
+    /// The sources of the network filters added with a `source`, keyed by
+    /// their id, the hash of their text. A filter added from several lists
+    /// has the source of each of them. Only filters from a `FilterSet` with
+    /// debugging enabled have a text.
+    filter_sources: HashMap<FilterId, Vec<String>>,
+    /// The sources of the cosmetic filters, keyed by their selector.
+    selector_sources: HashMap<String, Vec<String>>,
+    /// The resources added with `add_resource`, keyed by their name and
+    /// each of their aliases.
+    resources: HashMap<String, ResourceName>,
+}
+
+/// The name of a resource, as given to `add_resource`.
+#[derive(Serialize, Deserialize)]
+struct ResourceName {
+    /// The primary name of the resource.
+    name: String,
+    /// The hash of the `data:` URL the blocker redirects to.
+    data_url_hash: FilterId,
+}
+
+/// What the optimizer joins the texts of merged network filters with.
+const MERGED_FILTER_SEPARATOR: &str = " <+> ";
+/// Starts an engine serialized with `EngineExtras`, followed by
+/// `EXTRAS_VERSION`, the extras and the upstream format. The upstream format
+/// starts with its own magic number or a gzip header instead.
+const EXTRAS_MAGIC: &[u8] = b"\xFFpython-adblock-extras";
+/// The version of the `EngineExtras` format.
+const EXTRAS_VERSION: u8 = 1;
+
+impl EngineExtras {
+    fn is_empty(&self) -> bool {
+        self.filter_sources.is_empty()
+            && self.selector_sources.is_empty()
+            && self.resources.is_empty()
+    }
+
+    /// Returns the sources of a network filter matched by the blocker, given
+    /// its text, joined with `MERGED_FILTER_SEPARATOR`. A filter merged with
+    /// others by the optimizer has the texts of all of them, and their
+    /// sources are joined the same way.
+    fn filter_source(&self, text: &str) -> Option<String> {
+        let parts: Vec<&str> = if self.filter_sources.contains_key(&fast_hash(text)) {
+            vec![text]
+        } else {
+            text.split(MERGED_FILTER_SEPARATOR).collect()
+        };
+        let mut sources: Vec<&str> = Vec::new();
+        for part in parts {
+            for source in self
+                .filter_sources
+                .get(&fast_hash(part))
+                .into_iter()
+                .flatten()
+            {
+                if !sources.contains(&source.as_str()) {
+                    sources.push(source);
+                }
+            }
+        }
+        if sources.is_empty() {
+            None
+        } else {
+            Some(sources.join(MERGED_FILTER_SEPARATOR))
+        }
+    }
+
+    /// Writes the extras, followed by the upstream serialized `engine`.
+    /// Without extras, the upstream format is kept as is.
+    fn serialize(&self, engine: Vec<u8>) -> Result<Vec<u8>, BlockerError> {
+        if self.is_empty() {
+            return Ok(engine);
+        }
+        let mut serialized = EXTRAS_MAGIC.to_vec();
+        serialized.push(EXTRAS_VERSION);
+        rmp_serde::encode::write(&mut serialized, self)
+            .map_err(|_| BlockerError::SerializationError)?;
+        serialized.extend_from_slice(&engine);
+        Ok(serialized)
+    }
+
+    /// Reads the extras written by `serialize`, returning them along with the
+    /// upstream serialized engine following them.
+    fn deserialize(serialized: &[u8]) -> Result<(Self, &[u8]), BlockerError> {
+        let mut rest = match serialized.strip_prefix(EXTRAS_MAGIC) {
+            Some(rest) => rest,
+            None => return Ok((Self::default(), serialized)),
+        };
+        match rest.split_first() {
+            Some((&EXTRAS_VERSION, after_version)) => rest = after_version,
+            _ => return Err(BlockerError::DeserializationError),
+        }
+        let extras =
+            rmp_serde::from_read(&mut rest).map_err(|_| BlockerError::DeserializationError)?;
+        Ok((extras, rest))
+    }
+}
+
+impl EngineState {
+    /// Returns the name of the resource the blocker redirects `request` to,
+    /// given the `data:` URL it redirects to. The resource is named by the
+    /// redirect option of a matching filter. Without the filters, after
+    /// `deserialize`, only a resource whose content no other resource has
+    /// can be named.
+    fn redirect_resource_name(&self, request: &RustRequest, data_url: &str) -> Option<String> {
+        let data_url_hash = fast_hash(data_url);
+        // The blocker uses the first matching filter, but the order it checks
+        // them in isn't known, so the `data:` URL tells which one it was.
+        let resources = &self.extras.resources;
+        let name = self
+            .redirect_filters
+            .iter()
+            .filter(|filter| !filter.is_exception() && !filter.is_redirect_url())
+            .filter(|filter| filter.matches(request))
+            .filter_map(|filter| resources.get(filter.redirect.as_deref()?))
+            .find(|resource| resource.data_url_hash == data_url_hash)
+            .map(|resource| resource.name.clone());
+        if name.is_some() || !self.redirect_filters.is_empty() {
+            return name;
+        }
+        let mut names = resources
+            .values()
+            .filter(|resource| resource.data_url_hash == data_url_hash)
+            .map(|resource| &resource.name);
+        let name = names.next()?;
+        if names.all(|other| other == name) {
+            Some(name.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Converts a result from the blocker, filling in the name of the
+    /// resource it redirects to and the sources of the matched filters.
+    fn result(&self, blocker_result: RustBlockerResult, request: &RustRequest) -> BlockerResult {
+        let mut result = BlockerResult::from(blocker_result);
+        if let Some(filter) = &result.filter {
+            result.filter_source = self.extras.filter_source(filter);
+        }
+        if let Some(exception) = &result.exception {
+            result.exception_source = self.extras.filter_source(exception);
+        }
+        if result.redirect_type.as_deref() == Some("resource") {
+            result.redirect_resource_name = result
+                .redirect
+                .as_ref()
+                .and_then(|data_url| self.redirect_resource_name(request, data_url));
+        }
+        result
+    }
+
+    /// Checks `request`, see `Blocker::check_parameterised`.
+    fn check(
+        &self,
+        request: &RustRequest,
+        previously_matched_rule: bool,
+        force_check_exceptions: bool,
+    ) -> BlockerResult {
+        let blocker_result = self.engine.blocker.check_parameterised(
+            request,
+            previously_matched_rule,
+            force_check_exceptions,
+        );
+        self.result(blocker_result, request)
+    }
+
+    /// Checks a request given by its URLs, like
+    /// `RustEngine::check_network_urls`.
+    fn check_urls(&self, url: &str, source_url: &str, request_type: &str) -> BlockerResult {
+        match RustRequest::from_urls(url, source_url, request_type) {
+            Ok(request) => self.check(&request, false, false),
+            Err(_) => BlockerResult::from(RustBlockerResult {
+                matched: false,
+                important: false,
+                redirect: None,
+                exception: None,
+                filter: None,
+                error: Some("Error parsing request".to_string()),
+            }),
+        }
+    }
+}
+
+impl Engine {
+    /// Locks the engine's state for reading. Must not be called while
+    /// holding the GIL, since a writer may be waiting for the GIL while
+    /// holding the lock.
+    fn read(&self) -> RwLockReadGuard<'_, EngineState> {
+        self.state.read().expect("engine lock poisoned")
+    }
+
+    /// Locks the engine's state for writing. Must not be called while
+    /// holding the GIL, see `read`.
+    fn write(&self) -> RwLockWriteGuard<'_, EngineState> {
+        self.state.write().expect("engine lock poisoned")
+    }
+
+    /// The error raised by `method` when the engine has no `FilterLists`.
+    fn filters_not_kept(&self, method: &str) -> PyErr {
+        if self.keep_filters {
+            AdblockException::new_err(format!(
+                "Engine.{} is not available after deserialize(), since the filters are not serialized",
+                method
+            ))
+        } else {
+            AdblockException::new_err(format!(
+                "Engine.{} needs an Engine created with keep_filters=True",
+                method
+            ))
+        }
+    }
 }
 
 #[pymethods]
 impl Engine {
     /// Create a new adblocking engine
     #[new]
-    #[args(filter_set, optimize = true)]
-    pub fn new(filter_set: FilterSet, optimize: bool) -> Self {
-        let engine = RustEngine::from_filter_set(filter_set.filter_set, optimize);
-        Self { engine, optimize }
+    #[args(filter_set, optimize = true, keep_filters = false)]
+    pub fn new(filter_set: FilterSet, optimize: bool, keep_filters: bool) -> Self {
+        let mut extras = EngineExtras::default();
+        let network_filters = filter_set
+            .network_filters
+            .iter()
+            .zip(&filter_set.network_origins);
+        for (filter, origin) in network_filters {
+            if let (Some(text), Some(source)) = (&filter.raw_line, &origin.source) {
+                let sources = extras.filter_sources.entry(fast_hash(text)).or_default();
+                if !sources.contains(source) {
+                    sources.push(source.clone());
+                }
+            }
+        }
+        // Like `Blocker::new`, leave out the filters cancelled by `$badfilter`.
+        let badfilters: HashSet<_> = filter_set
+            .network_filters
+            .iter()
+            .filter(|filter| filter.is_badfilter())
+            .map(|filter| filter.get_id_without_badfilter())
+            .collect();
+        let redirect_filters = filter_set
+            .network_filters
+            .iter()
+            .filter(|filter| filter.is_redirect() && !filter.is_badfilter())
+            .filter(|filter| !badfilters.contains(&filter.get_id()))
+            .cloned()
+            .collect();
+        let cosmetic_filters = filter_set
+            .cosmetic_filters
+            .iter()
+            .zip(&filter_set.cosmetic_origins);
+        for (filter, origin) in cosmetic_filters {
+            let source = match &origin.source {
+                Some(source) => source,
+                None => continue,
+            };
+            if filter.mask.contains(CosmeticFilterMask::SCRIPT_INJECT) {
+                continue;
+            }
+            let sources = extras
+                .selector_sources
+                .entry(filter.selector.clone())
+                .or_default();
+            if !sources.contains(source) {
+                sources.push(source.clone());
+            }
+        }
+
+        // The upstream engine can only be created from a `FilterSet`. Since it
+        // only holds the cosmetic filters, the blocker it starts with is empty.
+        let mut engine = RustEngine::from_filter_set(filter_set.cosmetic_filter_set(), optimize);
+
+        let filter_lists = if keep_filters {
+            Some(FilterLists::new(filter_set.network_filters.clone()))
+        } else {
+            None
+        };
+        engine.blocker = Blocker::new(
+            filter_set.network_filters,
+            &BlockerOptions {
+                enable_optimizations: optimize,
+            },
+        );
+
+        Self {
+            state: RwLock::new(EngineState {
+                engine,
+                filter_lists,
+                redirect_filters,
+                extras,
+            }),
+            optimize,
+            keep_filters,
+        }
     }
 
     /// Check if the given `url`—pointing to a resource of type `request_type`—
@@ -442,17 +2203,165 @@ impl Engine {
     /// * `url` - The URL of the request to check
     /// * `source_url` - The URL from where the request is made
     /// * `request_type` - The resource type that the request points to
-    #[pyo3(text_signature = "($self, url, source_url, request_type)")]
+    /// * `strict` - Raise an exception for unknown request types and
+    ///   unparsable URLs
+    #[pyo3(text_signature = "($self, url, source_url, request_type, strict)")]
+    #[args(url, source_url, request_type, strict = "false")]
     pub fn check_network_urls(
         &self,
+        py: Python<'_>,
         url: &str,
         source_url: &str,
-        request_type: &str,
-    ) -> BlockerResult {
-        let blocker_result = self
-            .engine
-            .check_network_urls(url, source_url, request_type);
-        blocker_result.into()
+        request_type: RequestTypeArg<'_>,
+        strict: bool,
+    ) -> PyResult<BlockerResult> {
+        let request_type = request_type.resolve(strict)?;
+        if strict {
+            check_request_urls(py, url, source_url)?;
+        }
+        let blocker_result = py.allow_threads(|| {
+            let state = self.read();
+            state.check_urls(url, source_url, request_type)
+        });
+        Ok(blocker_result)
+    }
+
+    /// Check if the given, already parsed, `request` is blocked. Gives the
+    /// same result as `check_network_urls` called with the arguments the
+    /// request was created from.
+    #[pyo3(text_signature = "($self, request)")]
+    pub fn check(&self, py: Python<'_>, request: PyRef<'_, Request>) -> BlockerResult {
+        let request = &request.request;
+        py.allow_threads(|| {
+            let state = self.read();
+            state.check(request, false, false)
+        })
+    }
+
+    /// Explains how the engine decides whether the given request is blocked.
+    /// Returns an `Explanation` holding the `BlockerResult`, along with every
+    /// filter matching the request, in the order in which the engine
+    /// evaluates them:
+    /// * filters cancelled by a `$badfilter` rule
+    /// * `$important` filters, which are not subject to exceptions
+    /// * blocking filters, including `$tag` gated filters that were skipped
+    ///   because their tag is not enabled
+    /// * exceptions
+    /// * redirect candidates
+    ///
+    /// Filter texts are only available if the engine was created from a
+    /// `FilterSet` with debugging enabled. Requires an engine created with
+    /// `keep_filters=True`, and is not available after `deserialize`.
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the request to check
+    /// * `source_url` - The URL from where the request is made
+    /// * `request_type` - The resource type that the request points to
+    #[pyo3(text_signature = "($self, url, source_url, request_type)")]
+    pub fn explain(
+        &self,
+        py: Python<'_>,
+        url: &str,
+        source_url: &str,
+        request_type: RequestTypeArg<'_>,
+    ) -> PyResult<Explanation> {
+        let request_type = request_type.resolve(false)?;
+        let request = RustRequest::from_urls(url, source_url, request_type)
+            .map_err(|_| invalid_url_error(py, UrlArgument::Url, url))?;
+        let explanation = py.allow_threads(|| {
+            let state = self.read();
+            let filter_lists = state.filter_lists.as_ref()?;
+            let tags_enabled = state.engine.blocker.tags_enabled().into_iter().collect();
+            let matches = filter_lists.trace(&request, &tags_enabled);
+            Some(Explanation {
+                result: state.check(&request, false, false),
+                matches: matches.iter().map(ExplainedFilter::from).collect(),
+            })
+        });
+        explanation.ok_or_else(|| self.filters_not_kept("explain"))
+    }
+
+    /// Like `check_network_urls`, but returns every network filter that
+    /// applies to the request instead of a single decision. The filters are
+    /// grouped by role in a `FilterMatches` object, with the `block`,
+    /// `exception`, `important`, `redirect` and `csp` attributes. Filters
+    /// cancelled by `$badfilter`, and `$tag` gated filters whose tag is not
+    /// enabled, are left out.
+    ///
+    /// Filter texts are only available if the engine was created from a
+    /// `FilterSet` with debugging enabled. Requires an engine created with
+    /// `keep_filters=True`, and is not available after `deserialize`.
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the request to check
+    /// * `source_url` - The URL from where the request is made
+    /// * `request_type` - The resource type that the request points to
+    /// * `strict` - Raise an exception for unknown request types and
+    ///   unparsable URLs
+    #[pyo3(text_signature = "($self, url, source_url, request_type, strict)")]
+    #[args(url, source_url, request_type, strict = "false")]
+    pub fn check_network_urls_all_matches(
+        &self,
+        py: Python<'_>,
+        url: &str,
+        source_url: &str,
+        request_type: RequestTypeArg<'_>,
+        strict: bool,
+    ) -> PyResult<FilterMatches> {
+        let request_type = request_type.resolve(strict)?;
+        if strict {
+            check_request_urls(py, url, source_url)?;
+        }
+        let matches = py.allow_threads(|| {
+            let state = self.read();
+            let filter_lists = state.filter_lists.as_ref()?;
+            let request = match RustRequest::from_urls(url, source_url, request_type) {
+                Ok(request) => request,
+                Err(_) => return Some(FilterMatches::default()),
+            };
+            let tags_enabled = state.engine.blocker.tags_enabled().into_iter().collect();
+            Some(filter_lists.collect_all(&request, &tags_enabled))
+        });
+        matches.ok_or_else(|| self.filters_not_kept("check_network_urls_all_matches"))
+    }
+
+    /// Check a batch of requests at once. Each request is given as a tuple of
+    /// `(url, source_url, request_type)`, with the same meaning as the
+    /// arguments of `check_network_urls`. The GIL is released while the
+    /// checks run. Returns a list of `BlockerResult`s, in the same order as
+    /// the given requests.
+    ///
+    /// # Arguments
+    /// * `requests` - The `(url, source_url, request_type)` tuples to check
+    /// * `strict` - Raise an exception for unknown request types and
+    ///   unparsable URLs
+    #[pyo3(text_signature = "($self, requests, strict)")]
+    #[args(requests, strict = "false")]
+    pub fn check_network_urls_batch(
+        &self,
+        py: Python<'_>,
+        requests: Vec<(&str, &str, RequestTypeArg<'_>)>,
+        strict: bool,
+    ) -> PyResult<Vec<BlockerResult>> {
+        let requests = requests
+            .into_iter()
+            .map(|(url, source_url, request_type)| {
+                let request_type = request_type.resolve(strict)?;
+                if strict {
+                    check_request_urls(py, url, source_url)?;
+                }
+                Ok((url, source_url, request_type))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(py.allow_threads(|| {
+            let state = self.read();
+            requests
+                .iter()
+                .map(|(url, source_url, request_type)| {
+                    state.check_urls(url, source_url, request_type)
+                })
+                .collect()
+        }))
     }
 
     /// Check if a request should be blocked based on the given parameters.
@@ -465,25 +2374,43 @@ impl Engine {
     /// * `third_party_request` - Is the given request to a third-party? Here,
     ///   `None` can be given and the engine will figure it out based on the
     ///   `hostname` and `source_hostname`.
+    /// * `strict` - Raise an exception for unknown request types. The
+    ///   hostnames are not validated.
     #[pyo3(
-        text_signature = "($self, url, hostname, source_hostname, requsest_type, third_party_request)"
+        text_signature = "($self, url, hostname, source_hostname, requsest_type, third_party_request, strict)"
+    )]
+    #[args(
+        url,
+        hostname,
+        source_hostname,
+        request_type,
+        third_party_request,
+        strict = "false"
     )]
+    #[allow(clippy::too_many_arguments)]
     pub fn check_network_urls_with_hostnames(
         &self,
+        py: Python<'_>,
         url: &str,
         hostname: &str,
         source_hostname: &str,
-        request_type: &str,
+        request_type: RequestTypeArg<'_>,
         third_party_request: Option<bool>,
-    ) -> BlockerResult {
-        let blocker_result = self.engine.check_network_urls_with_hostnames(
-            url,
-            hostname,
-            source_hostname,
-            request_type,
-            third_party_request,
-        );
-        blocker_result.into()
+        strict: bool,
+    ) -> PyResult<BlockerResult> {
+        let request_type = request_type.resolve(strict)?;
+        let blocker_result = py.allow_threads(|| {
+            let state = self.read();
+            let request = RustRequest::from_urls_with_hostname(
+                url,
+                hostname,
+                source_hostname,
+                request_type,
+                third_party_request,
+            );
+            state.check(&request, false, false)
+        });
+        Ok(blocker_result)
     }
 
     /// Check if a request should be blocked based on the given parameters.
@@ -499,31 +2426,48 @@ impl Engine {
     /// * `previously_matched_rule` - Return a match as long as there are no
     ///    exceptions
     /// * `force_check_exceptions` - Check exceptions even if no other rule matches
+    /// * `strict` - Raise an exception for unknown request types. The
+    ///   hostnames are not validated.
     #[pyo3(
         text_signature = "($self, url, hostname, source_hostname, request_type, \
-        third_party_request, previously_matched_rule, force_check_exceptions)"
+        third_party_request, previously_matched_rule, force_check_exceptions, strict)"
+    )]
+    #[args(
+        url,
+        hostname,
+        source_hostname,
+        request_type,
+        third_party_request,
+        previously_matched_rule,
+        force_check_exceptions,
+        strict = "false"
     )]
     #[allow(clippy::too_many_arguments)]
     pub fn check_network_urls_with_hostnames_subset(
         &self,
+        py: Python<'_>,
         url: &str,
         hostname: &str,
         source_hostname: &str,
-        request_type: &str,
+        request_type: RequestTypeArg<'_>,
         third_party_request: Option<bool>,
         previously_matched_rule: bool,
         force_check_exceptions: bool,
-    ) -> BlockerResult {
-        let blocker_result = self.engine.check_network_urls_with_hostnames_subset(
-            url,
-            hostname,
-            source_hostname,
-            request_type,
-            third_party_request,
-            previously_matched_rule,
-            force_check_exceptions,
-        );
-        blocker_result.into()
+        strict: bool,
+    ) -> PyResult<BlockerResult> {
+        let request_type = request_type.resolve(strict)?;
+        let blocker_result = py.allow_threads(|| {
+            let state = self.read();
+            let request = RustRequest::from_urls_with_hostname(
+                url,
+                hostname,
+                source_hostname,
+                request_type,
+                third_party_request,
+            );
+            state.check(&request, previously_matched_rule, force_check_exceptions)
+        });
+        Ok(blocker_result)
     }
 
     /// Sets this engine's resources to additionally include `resource`.
@@ -536,13 +2480,14 @@ impl Engine {
     /// * `aliases`: List of aliases for the resource
     #[pyo3(text_signature = "($self, name, content_type, content, aliases)")]
     pub fn add_resource(
-        &mut self,
+        &self,
+        py: Python<'_>,
         name: &str,
         content_type: &str,
         content: &str,
         aliases: Option<Vec<String>>,
     ) -> PyResult<()> {
-        let result = self.engine.add_resource(Resource {
+        let resource = Resource {
             name: name.to_string(),
             aliases: aliases.unwrap_or_default(),
             kind: match content_type {
@@ -552,6 +2497,34 @@ impl Engine {
                 ))),
             },
             content: content.to_string(),
+        };
+        let data_url = match &resource.kind {
+            ResourceType::Mime(mime_type) => Some(format!(
+                "data:{};base64,{}",
+                String::from(mime_type.clone()),
+                resource.content
+            )),
+            ResourceType::Template => None,
+        };
+        let names: Vec<String> = std::iter::once(name.to_string())
+            .chain(resource.aliases.iter().cloned())
+            .collect();
+        let result = py.allow_threads(|| {
+            let mut state = self.write();
+            state.engine.add_resource(resource)?;
+            if let Some(data_url) = data_url {
+                let data_url_hash = fast_hash(data_url.trim());
+                for alias in names {
+                    state.extras.resources.insert(
+                        alias,
+                        ResourceName {
+                            name: name.to_string(),
+                            data_url_hash,
+                        },
+                    );
+                }
+            }
+            Ok(())
         });
 
         match result {
@@ -569,30 +2542,34 @@ impl Engine {
 
     /// Serialize this blocking engine to bytes. They can then be deserialized
     /// using `deserialize()` to get the same engine again.
+    ///
+    /// Engines with filters added with a `source`, or with resources added
+    /// with `add_resource()`, are written in a format that only this version
+    /// of the library and later ones can read, since it also keeps the sources
+    /// and resource names. Other engines are written in the format of the
+    /// underlying Rust library.
     #[pyo3(text_signature = "($self)")]
-    pub fn serialize<'p>(&mut self, py: Python<'p>) -> PyResult<&'p PyBytes> {
-        let bytes = self.serialize_inner()?;
+    pub fn serialize<'p>(&self, py: Python<'p>) -> PyResult<&'p PyBytes> {
+        let bytes = self.serialize_inner(py)?;
         let py_bytes = PyBytes::new(py, &bytes);
         Ok(py_bytes)
     }
 
-    fn serialize_inner(&mut self) -> PyResult<Vec<u8>> {
-        let result = self.engine.serialize_raw();
-        match result {
-            Ok(x) => Ok(x),
-            Err(error) => {
-                let my_blocker_error: BlockerError = error.into();
-                Err(my_blocker_error.into())
-            }
-        }
+    fn serialize_inner(&self, py: Python<'_>) -> PyResult<Vec<u8>> {
+        let result = py.allow_threads(|| {
+            let state = self.read();
+            let engine = state.engine.serialize_raw()?;
+            state.extras.serialize(engine)
+        });
+        Ok(result?)
     }
 
     /// Serialize this blocking engine to a file. The file can then be
     /// deserialized using `deserialize_from_file()` to get the same engine
     /// again.
     #[pyo3(text_signature = "($self, file)")]
-    pub fn serialize_to_file(&mut self, file: &str) -> PyResult<()> {
-        let data = self.serialize_inner()?;
+    pub fn serialize_to_file(&self, py: Python<'_>, file: &str) -> PyResult<()> {
+        let data = self.serialize_inner(py)?;
         let mut fd = fs::OpenOptions::new()
             .create(true)
             .truncate(true)
@@ -604,31 +2581,33 @@ impl Engine {
 
     /// Deserialize a blocking engine from bytes produced with `serialize()`.
     #[pyo3(text_signature = "($self, serialized)")]
-    pub fn deserialize(&mut self, serialized: &[u8]) -> PyResult<()> {
-        let result = self.engine.deserialize(serialized);
-        match result {
-            Ok(_) => Ok(()),
-            Err(error) => {
-                let my_blocker_error: BlockerError = error.into();
-                Err(my_blocker_error.into())
-            }
-        }
+    pub fn deserialize(&self, py: Python<'_>, serialized: &[u8]) -> PyResult<()> {
+        let result = py.allow_threads(|| {
+            let (extras, serialized) = EngineExtras::deserialize(serialized)?;
+            let mut state = self.write();
+            state.engine.deserialize(serialized)?;
+            state.filter_lists = None;
+            state.redirect_filters = Vec::new();
+            state.extras = extras;
+            Ok::<_, BlockerError>(())
+        });
+        Ok(result?)
     }
 
     /// Deserialize a blocking engine from file produced with
     /// `serialize_to_file()`.
     #[pyo3(text_signature = "($self, file)")]
-    pub fn deserialize_from_file(&mut self, file: &str) -> PyResult<()> {
+    pub fn deserialize_from_file(&self, py: Python<'_>, file: &str) -> PyResult<()> {
         let mut fd = fs::File::open(file)?;
         let mut data: Vec<u8> = Vec::new();
         fd.read_to_end(&mut data)?;
-        self.deserialize(&data)
+        self.deserialize(py, &data)
     }
 
     /// Checks if the given filter exists in the blocking engine.
     #[pyo3(text_signature = "($self, filter)")]
-    pub fn filter_exists(&self, filter: &str) -> bool {
-        self.engine.filter_exists(filter)
+    pub fn filter_exists(&self, py: Python<'_>, filter: &str) -> bool {
+        py.allow_threads(|| self.read().engine.filter_exists(filter))
     }
 
     /// Sets this engine's tags to be _only_ the ones provided in tags.
@@ -636,8 +2615,8 @@ impl Engine {
     /// Tags can be used to cheaply enable or disable network rules with a
     /// corresponding $tag option.
     #[pyo3(text_signature = "($self, tags)")]
-    pub fn use_tags(&mut self, tags: Vec<&str>) {
-        self.engine.use_tags(&tags);
+    pub fn use_tags(&self, py: Python<'_>, tags: Vec<&str>) {
+        py.allow_threads(|| self.write().engine.use_tags(&tags));
     }
 
     /// Sets this engine's tags to additionally include the ones provided in
@@ -646,8 +2625,8 @@ impl Engine {
     /// Tags can be used to cheaply enable or disable network rules with a
     /// corresponding $tag option.
     #[pyo3(text_signature = "($self, tags)")]
-    pub fn enable_tags(&mut self, tags: Vec<&str>) {
-        self.engine.enable_tags(&tags);
+    pub fn enable_tags(&self, py: Python<'_>, tags: Vec<&str>) {
+        py.allow_threads(|| self.write().engine.enable_tags(&tags));
     }
 
     /// Sets this engine's tags to no longer include the ones provided in
@@ -656,8 +2635,8 @@ impl Engine {
     /// Tags can be used to cheaply enable or disable network rules with a
     /// corresponding $tag option.
     #[pyo3(text_signature = "($self, tags)")]
-    pub fn disable_tags(&mut self, tags: Vec<&str>) {
-        self.engine.disable_tags(&tags);
+    pub fn disable_tags(&self, py: Python<'_>, tags: Vec<&str>) {
+        py.allow_threads(|| self.write().engine.disable_tags(&tags));
     }
 
     /// Checks if a given tag exists in this engine.
@@ -665,8 +2644,8 @@ impl Engine {
     /// Tags can be used to cheaply enable or disable network rules with a
     /// corresponding $tag option.
     #[pyo3(text_signature = "($self, tag)")]
-    pub fn tag_exists(&self, tag: &str) -> bool {
-        self.engine.tag_exists(tag)
+    pub fn tag_exists(&self, py: Python<'_>, tag: &str) -> bool {
+        py.allow_threads(|| self.read().engine.tag_exists(tag))
     }
 
     /// Returns a set of cosmetic filter resources required for a particular
@@ -674,8 +2653,23 @@ impl Engine {
     /// page should be passed to hidden_class_id_selectors to obtain any
     /// stylesheets consisting of generic rules.
     #[pyo3(text_signature = "($self, url)")]
-    pub fn url_cosmetic_resources(&self, url: &str) -> UrlSpecificResources {
-        self.engine.url_cosmetic_resources(url).into()
+    pub fn url_cosmetic_resources(&self, py: Python<'_>, url: &str) -> UrlSpecificResources {
+        py.allow_threads(|| {
+            let state = self.read();
+            let mut resources =
+                UrlSpecificResources::from(state.engine.url_cosmetic_resources(url));
+            resources.selector_sources = resources
+                .hide_selectors
+                .iter()
+                .chain(resources.style_selectors.keys())
+                .chain(&resources.exceptions)
+                .filter_map(|selector| {
+                    let sources = state.extras.selector_sources.get(selector)?;
+                    Some((selector.clone(), sources.clone()))
+                })
+                .collect();
+            resources
+        })
     }
 
     /// If any of the provided CSS classes or ids could cause a certain generic
@@ -688,13 +2682,16 @@ impl Engine {
     #[pyo3(text_signature = "($self, classes, ids, exceptions)")]
     pub fn hidden_class_id_selectors(
         &self,
+        py: Python<'_>,
         classes: Vec<String>,
         ids: Vec<String>,
         exceptions: HashSet<String>,
     ) -> PyResult<Vec<String>> {
-        Ok(self
-            .engine
-            .hidden_class_id_selectors(&classes, &ids, &exceptions))
+        Ok(py.allow_threads(|| {
+            self.read()
+                .engine
+                .hidden_class_id_selectors(&classes, &ids, &exceptions)
+        }))
     }
 
     fn __repr__(&self) -> PyResult<String> {
@@ -734,6 +2731,12 @@ impl DiyPythonRepr for String {
     }
 }
 
+impl DiyPythonRepr for &str {
+    fn diy_python_repr(&self) -> String {
+        self.to_string().diy_python_repr()
+    }
+}
+
 impl DiyPythonRepr for bool {
     fn diy_python_repr(&self) -> String {
         if *self {
@@ -0,0 +1,467 @@
+//! Static analysis of the filters in a `FilterSet`, used by `FilterSet.lint`.
+
+use adblock::blocker::{Blocker, BlockerOptions};
+use adblock::filters::cosmetic::{CosmeticFilter, CosmeticFilterMask};
+use adblock::filters::network::{FilterPart, NetworkFilter};
+use adblock::request::Request as RustRequest;
+use adblock::utils::{fast_hash, Hash};
+use pyo3::prelude::*;
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::{cosmetic_text, filter, DiyPythonRepr, FilterOrigin};
+
+/// Patterns with fewer letters and digits than this, and no hostname or
+/// `$domain` restriction, match too many requests.
+const MIN_PATTERN_CHARACTERS: usize = 4;
+
+/// The request types unused exceptions are checked against.
+const REQUEST_TYPES: &[&str] = &[
+    "document",
+    "subdocument",
+    "script",
+    "image",
+    "stylesheet",
+    "xmlhttprequest",
+    "font",
+    "media",
+    "object",
+    "ping",
+    "websocket",
+    "other",
+];
+
+/// A possible problem with a filter, found by `FilterSet.lint`.
+#[pyclass]
+#[derive(Clone)]
+pub struct LintFinding {
+    /// The kind of problem, one of:
+    /// * `"duplicate"` - The filter is the same as an earlier one.
+    /// * `"semantic_duplicate"` - The filter is written differently, but
+    ///   means the same as an earlier one.
+    /// * `"badfiltered"` - The filter is cancelled by a `$badfilter` rule.
+    /// * `"unused_exception"` - No blocking filter matches what the
+    ///   exception allows.
+    /// * `"broad_pattern"` - The filter matches a short pattern anywhere,
+    ///   on any site.
+    /// * `"slow_regex"` - The filter has no token the engine can index it
+    ///   by, so it is checked against every request.
+    /// * `"invalid_selector"` - The selector of the cosmetic filter is not
+    ///   valid CSS.
+    #[pyo3(get)]
+    pub kind: &'static str,
+    /// A description of the problem.
+    #[pyo3(get)]
+    pub message: String,
+    /// The text of the filter, or `None` for network filters in a
+    /// `FilterSet` without debugging enabled.
+    #[pyo3(get)]
+    pub text: Option<String>,
+    /// The 1-based line number of the filter in the list it was added from.
+    #[pyo3(get)]
+    pub line_number: usize,
+    /// The name of the list the filter was included from with an
+    /// `!#include` directive, if any.
+    #[pyo3(get)]
+    pub include: Option<String>,
+    /// The `source` the filter was added with, if any.
+    #[pyo3(get)]
+    pub source: Option<String>,
+}
+
+#[pymethods]
+impl LintFinding {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "LintFinding(kind={}, line_number={}, source={}, text={}, message={})",
+            self.kind.diy_python_repr(),
+            self.line_number,
+            self.source.diy_python_repr(),
+            self.text.diy_python_repr(),
+            self.message.diy_python_repr(),
+        ))
+    }
+}
+
+/// Describes where a filter came from, for messages referring to another
+/// filter.
+fn describe(origin: &FilterOrigin) -> String {
+    let mut description = format!("line {}", origin.line_number);
+    if let Some(include) = &origin.include {
+        description.push_str(&format!(" of {:?}", include));
+    }
+    if let Some(source) = &origin.source {
+        description.push_str(&format!(" in {:?}", source));
+    }
+    description
+}
+
+/// Collects findings, keeping them in the order the filters were added.
+struct Findings<'a> {
+    network_origins: &'a [FilterOrigin],
+    cosmetic_origins: &'a [FilterOrigin],
+    /// The findings, along with the position of their filter in the
+    /// `FilterSet`, network filters first.
+    findings: Vec<(usize, LintFinding)>,
+}
+
+impl Findings<'_> {
+    fn push(
+        &mut self,
+        position: usize,
+        origin: &FilterOrigin,
+        text: Option<String>,
+        kind: &'static str,
+        message: String,
+    ) {
+        self.findings.push((
+            position,
+            LintFinding {
+                kind,
+                message,
+                text,
+                line_number: origin.line_number,
+                include: origin.include.clone(),
+                source: origin.source.clone(),
+            },
+        ));
+    }
+
+    fn network(
+        &mut self,
+        index: usize,
+        filter: &NetworkFilter,
+        kind: &'static str,
+        message: String,
+    ) {
+        let origin = &self.network_origins[index];
+        let text = filter.raw_line.as_ref().map(|line| line.to_string());
+        self.push(index, origin, text, kind, message);
+    }
+
+    fn cosmetic(&mut self, index: usize, text: &str, kind: &'static str, message: String) {
+        let origin = &self.cosmetic_origins[index];
+        let position = self.network_origins.len() + index;
+        self.push(position, origin, Some(text.to_string()), kind, message);
+    }
+
+    fn into_vec(mut self) -> Vec<LintFinding> {
+        self.findings.sort_by_key(|(position, _)| *position);
+        self.findings
+            .into_iter()
+            .map(|(_, finding)| finding)
+            .collect()
+    }
+}
+
+/// Lints the filters of a `FilterSet`.
+pub(crate) fn lint(
+    network_filters: &[NetworkFilter],
+    network_origins: &[FilterOrigin],
+    cosmetic_filters: &[CosmeticFilter],
+    cosmetic_origins: &[FilterOrigin],
+) -> Vec<LintFinding> {
+    let mut findings = Findings {
+        network_origins,
+        cosmetic_origins,
+        findings: Vec::new(),
+    };
+    lint_network_duplicates(&mut findings, network_filters);
+    lint_badfilters(&mut findings, network_filters);
+    lint_unused_exceptions(&mut findings, network_filters);
+    for (index, filter) in network_filters.iter().enumerate() {
+        if is_broad(filter) {
+            findings.network(
+                index,
+                filter,
+                "broad_pattern",
+                "matches a short pattern on every site, add a hostname or a $domain option".into(),
+            );
+        }
+        if (filter.is_regex() || filter.is_complete_regex()) && !has_tokens(filter) {
+            findings.network(
+                index,
+                filter,
+                "slow_regex",
+                "has no token to index it by, so it is checked against every request".into(),
+            );
+        }
+    }
+    lint_cosmetic_filters(&mut findings, cosmetic_filters);
+    findings.into_vec()
+}
+
+fn lint_network_duplicates(findings: &mut Findings<'_>, filters: &[NetworkFilter]) {
+    // `get_id` leaves out the redirect resource and the tag, like in
+    // `FilterSet.merge`.
+    let mut seen = HashMap::new();
+    for (index, filter) in filters.iter().enumerate() {
+        let key = (filter.get_id(), &filter.redirect, &filter.tag);
+        let first = match seen.entry(key) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                entry.insert(index);
+                continue;
+            }
+        };
+        let first_filter: &NetworkFilter = &filters[first];
+        // Without debugging, the text of the filters isn't known.
+        let kind = match (&filter.raw_line, &first_filter.raw_line) {
+            (Some(text), Some(first_text)) if text != first_text => "semantic_duplicate",
+            _ => "duplicate",
+        };
+        let message = format!(
+            "same as the filter on {}",
+            describe(&findings.network_origins[first])
+        );
+        findings.network(index, filter, kind, message);
+    }
+}
+
+fn lint_badfilters(findings: &mut Findings<'_>, filters: &[NetworkFilter]) {
+    let badfilters: HashMap<Hash, usize> = filters
+        .iter()
+        .enumerate()
+        .filter(|(_, filter)| filter.is_badfilter())
+        .map(|(index, filter)| (filter.get_id_without_badfilter(), index))
+        .collect();
+    if badfilters.is_empty() {
+        return;
+    }
+    for (index, filter) in filters.iter().enumerate() {
+        if filter.is_badfilter() {
+            continue;
+        }
+        if let Some(&badfilter) = badfilters.get(&filter.get_id()) {
+            let message = format!(
+                "cancelled by the $badfilter on {}",
+                describe(&findings.network_origins[badfilter])
+            );
+            findings.network(index, filter, "badfiltered", message);
+        }
+    }
+}
+
+/// Flags exceptions for which no blocking filter matches an example URL
+/// built from the exception's pattern. Exceptions whose URLs can't be
+/// reconstructed, such as regexes or ones restricted to some `$domain`s,
+/// are skipped. The `$domain` options of blocking filters are ignored, since
+/// only hashes of their domains are kept, which can't be made into a source
+/// URL to check the exception with.
+fn lint_unused_exceptions(findings: &mut Findings<'_>, filters: &[NetworkFilter]) {
+    let candidates: Vec<(usize, String)> = filters
+        .iter()
+        .enumerate()
+        .filter(|(_, filter)| {
+            filter.is_exception()
+                && !filter.is_badfilter()
+                && !filter.is_generic_hide()
+                && !filter.is_csp()
+                && !filter.is_redirect()
+                && filter.opt_domains.is_none()
+        })
+        .filter_map(|(index, filter)| Some((index, example_url(filter)?)))
+        .collect();
+    if candidates.is_empty() {
+        return;
+    }
+
+    let mut tags: Vec<&str> = filters
+        .iter()
+        .filter(|filter| !filter.is_exception())
+        .filter_map(|filter| filter.tag.as_deref())
+        .collect();
+    tags.sort_unstable();
+    tags.dedup();
+    let blocking = filters
+        .iter()
+        .filter(|filter| !filter.is_exception())
+        .map(|filter| {
+            let mut filter = filter.clone();
+            filter.opt_domains = None;
+            filter.opt_not_domains = None;
+            filter.opt_domains_union = None;
+            filter.opt_not_domains_union = None;
+            filter
+        })
+        .collect();
+    let mut blocker = Blocker::new(
+        blocking,
+        &BlockerOptions {
+            enable_optimizations: false,
+        },
+    );
+    blocker.use_tags(&tags);
+
+    for (index, url) in candidates {
+        // Try the request both as a first and as a third party one.
+        let url = url.as_str();
+        let is_blocked = [url, "https://lint.example/"].iter().any(|source_url| {
+            REQUEST_TYPES.iter().any(|request_type| {
+                matches!(
+                    RustRequest::from_urls(url, source_url, request_type),
+                    Ok(request) if blocker.check(&request).matched
+                )
+            })
+        });
+        if !is_blocked {
+            findings.network(
+                index,
+                &filters[index],
+                "unused_exception",
+                "no blocking filter matches what this exception allows".into(),
+            );
+        }
+    }
+}
+
+/// Builds a URL the pattern of `filter` matches, if that is simple enough.
+fn example_url(filter: &NetworkFilter) -> Option<String> {
+    if filter.is_complete_regex() {
+        return None;
+    }
+    let pattern = match &filter.filter {
+        FilterPart::Empty => "",
+        FilterPart::Simple(pattern) => pattern,
+        FilterPart::AnyOf(_) => return None,
+    };
+    let path = pattern.replace('*', "").replace('^', "/");
+    let url = match &filter.hostname {
+        Some(hostname) if hostname.contains('*') => return None,
+        Some(hostname) => format!("https://{}/{}", hostname, path.trim_start_matches('/')),
+        None if path.contains("://") => path,
+        None => format!("https://example.com/{}", path.trim_start_matches('/')),
+    };
+    Some(url)
+}
+
+/// Whether `filter` blocks requests matching a short pattern on any site.
+/// Filters with only options, such as `$third-party,script`, have no pattern
+/// and are left out.
+fn is_broad(filter: &NetworkFilter) -> bool {
+    if filter.is_exception()
+        || filter.is_badfilter()
+        || filter.is_complete_regex()
+        || filter.hostname.is_some()
+        || filter.opt_domains.is_some()
+    {
+        return false;
+    }
+    let characters = match &filter.filter {
+        FilterPart::Simple(pattern) => pattern.chars().filter(|c| c.is_alphanumeric()).count(),
+        FilterPart::Empty | FilterPart::AnyOf(_) => return false,
+    };
+    characters < MIN_PATTERN_CHARACTERS
+}
+
+/// Whether the engine can index `filter` by a token, other than the scheme
+/// of the URL.
+fn has_tokens(filter: &NetworkFilter) -> bool {
+    let schemes = [fast_hash("http"), fast_hash("https")];
+    filter
+        .get_tokens()
+        .iter()
+        .flatten()
+        .any(|token| !schemes.contains(token))
+}
+
+fn lint_cosmetic_filters(findings: &mut Findings<'_>, filters: &[CosmeticFilter]) {
+    let mut seen_text = HashMap::new();
+    let mut seen_meaning = HashMap::new();
+    for (index, filter) in filters.iter().enumerate() {
+        let text = cosmetic_text(filter);
+        if let Some(&first) = seen_text.get(text) {
+            let message = format!(
+                "same as the filter on {}",
+                describe(&findings.cosmetic_origins[first])
+            );
+            findings.cosmetic(index, text, "duplicate", message);
+            continue;
+        }
+        seen_text.insert(text, index);
+
+        let is_scriptlet = filter.mask.contains(CosmeticFilterMask::SCRIPT_INJECT);
+        if !is_scriptlet && !is_valid_selector(&filter.selector) {
+            let message = format!("{:?} is not a valid CSS selector", filter.selector);
+            findings.cosmetic(index, text, "invalid_selector", message);
+        }
+
+        // Filters with the same meaning have the same canonical form.
+        let meaning = filter::canonical_cosmetic(filter, text);
+        match seen_meaning.entry(meaning) {
+            Entry::Occupied(entry) => {
+                let message = format!(
+                    "same as the filter on {}",
+                    describe(&findings.cosmetic_origins[*entry.get()])
+                );
+                findings.cosmetic(index, text, "semantic_duplicate", message);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(index);
+            }
+        }
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '-' || c == '_' || c == '\\' || !c.is_ascii()
+}
+
+/// Checks the basic syntax of a CSS selector: brackets and quotes must be
+/// balanced, combinators and commas must sit between selectors, and class,
+/// id and pseudo-class names must not be empty. The contents of brackets,
+/// such as attribute values or arguments of `:has()`, aren't checked further.
+fn is_valid_selector(selector: &str) -> bool {
+    let mut chars = selector.trim().chars().peekable();
+    let mut brackets = Vec::new();
+    let mut quote = None;
+    // The last character outside of brackets and quotes, ignoring spaces.
+    let mut previous = None;
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' | '\'' if !brackets.is_empty() => quote = Some(c),
+            '"' | '\'' | '{' | '}' | ';' => return false,
+            '[' | '(' => brackets.push(c),
+            ']' | ')' => {
+                let open = if c == ']' { '[' } else { '(' };
+                if brackets.pop() != Some(open) {
+                    return false;
+                }
+            }
+            _ if !brackets.is_empty() || c.is_whitespace() => (),
+            ',' | '>' | '+' | '~' => {
+                if matches!(previous, None | Some(',' | '>' | '+' | '~')) {
+                    return false;
+                }
+            }
+            '.' | '#' | ':' => {
+                // Pseudo-elements start with two colons.
+                if c == ':' && chars.peek() == Some(&':') {
+                    chars.next();
+                }
+                if !matches!(chars.peek(), Some(&next) if is_identifier_start(next)) {
+                    return false;
+                }
+            }
+            _ => (),
+        }
+        if brackets.is_empty() && !c.is_whitespace() {
+            previous = Some(c);
+        }
+    }
+    quote.is_none()
+        && brackets.is_empty()
+        && !matches!(previous, None | Some(',' | '>' | '+' | '~'))
+}
@@ -0,0 +1,143 @@
+//! Parsing of the `! Key: value` header comments at the top of filter lists.
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::DiyPythonRepr;
+
+/// The largest `! Expires:` value, in seconds, that fits in a Python
+/// `timedelta`, which is at most 999999999 days.
+const MAX_EXPIRES_SECONDS: u64 = 999_999_999 * 24 * 60 * 60;
+
+/// Parses the value of an `! Expires:` header, such as `4 days` or
+/// `12 hours (update frequency)`, into a number of seconds. Values too large
+/// for a `timedelta` are rejected.
+fn parse_expires(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let amount = parts.next()?.parse::<u64>().ok().filter(|&n| n > 0)?;
+    let unit_seconds = match parts.next()? {
+        "hour" | "hours" => 60 * 60,
+        "day" | "days" => 24 * 60 * 60,
+        _ => return None,
+    };
+    amount
+        .checked_mul(unit_seconds)
+        .filter(|&seconds| seconds <= MAX_EXPIRES_SECONDS)
+}
+
+/// The metadata found in the header comments of a filter list, such as
+/// `! Title: EasyList`. Fields are `None` when the list doesn't set them.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct FilterListMetadata {
+    /// `! Title:` - The name of the filter list.
+    #[pyo3(get)]
+    pub title: Option<String>,
+    /// `! Homepage:` - The webpage linked to as the filter list's homepage.
+    #[pyo3(get)]
+    pub homepage: Option<String>,
+    /// `! Version:` - The version of the filter list.
+    #[pyo3(get)]
+    pub version: Option<String>,
+    /// `! Last modified:` - When the filter list was last changed, as given
+    /// in the list.
+    #[pyo3(get)]
+    pub last_modified: Option<String>,
+    /// `! Redirect:` - The new address of a filter list that has moved.
+    #[pyo3(get)]
+    pub redirect: Option<String>,
+    expires_seconds: Option<u64>,
+    /// Whether a filter rule was seen, which ends the header.
+    header_ended: bool,
+}
+
+impl FilterListMetadata {
+    /// Attempts to add a line of a filter list to this metadata. Only header
+    /// comments with a known key are used, and values found in earlier lines
+    /// are kept. The header ends at the first line that is not a comment, an
+    /// empty line or an `[Adblock Plus 2.0]` style line.
+    pub(crate) fn try_add(&mut self, line: &str) {
+        if self.header_ended {
+            return;
+        }
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('[') {
+            return;
+        }
+        let header = match line.strip_prefix('!') {
+            Some(header) => header,
+            None => {
+                self.header_ended = true;
+                return;
+            }
+        };
+        let (key, value) = match header.split_once(':') {
+            Some((key, value)) => (key.trim().to_ascii_lowercase(), value.trim()),
+            None => return,
+        };
+        if value.is_empty() {
+            return;
+        }
+        let field = match key.as_str() {
+            "title" => &mut self.title,
+            "homepage" => &mut self.homepage,
+            "version" => &mut self.version,
+            "last modified" => &mut self.last_modified,
+            "redirect" => &mut self.redirect,
+            "expires" => {
+                if self.expires_seconds.is_none() {
+                    self.expires_seconds = parse_expires(value);
+                }
+                return;
+            }
+            _ => return,
+        };
+        if field.is_none() {
+            *field = Some(value.to_string());
+        }
+    }
+}
+
+#[pymethods]
+impl FilterListMetadata {
+    /// `! Expires:` - How often the filter list should be updated, as a
+    /// `datetime.timedelta`. `None` if the header is missing, its value is
+    /// not given in hours or days, or it is too large for a `timedelta`.
+    #[getter]
+    pub fn expires(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let seconds = match self.expires_seconds {
+            Some(seconds) => seconds,
+            None => return Ok(None),
+        };
+        // `PyDelta` isn't part of the limited API, so go through Python.
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("seconds", seconds)?;
+        let timedelta = py
+            .import("datetime")?
+            .getattr("timedelta")?
+            .call((), Some(kwargs))?;
+        Ok(Some(timedelta.into()))
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "FilterListMetadata(title={}, homepage={}, version={}, last_modified={}, redirect={}, expires={})",
+            self.title.diy_python_repr(),
+            self.homepage.diy_python_repr(),
+            self.version.diy_python_repr(),
+            self.last_modified.diy_python_repr(),
+            self.redirect.diy_python_repr(),
+            self.expires_seconds.map_or_else(|| "None".to_string(), timedelta_repr),
+        ))
+    }
+}
+
+/// Formats a number of seconds the way Python formats a `timedelta`.
+fn timedelta_repr(seconds: u64) -> String {
+    const DAY: u64 = 24 * 60 * 60;
+    match (seconds / DAY, seconds % DAY) {
+        (days, 0) => format!("datetime.timedelta(days={})", days),
+        (0, seconds) => format!("datetime.timedelta(seconds={})", seconds),
+        (days, seconds) => format!("datetime.timedelta(days={}, seconds={})", days, seconds),
+    }
+}
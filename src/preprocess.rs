@@ -0,0 +1,241 @@
+//! Support for the `!#if`, `!#else`, `!#endif` and `!#include` preprocessor
+//! directives used by uBlock Origin and AdGuard filter lists.
+
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::formats::ListOptions;
+use crate::report::ParseReport;
+use crate::FilterSet;
+
+/// Where the lists named by `!#include` directives are read from.
+pub enum IncludeResolver {
+    /// A Python callable, called with the name of the included list. Returns
+    /// its contents, or `None` if the list can't be found.
+    Callable(PyObject),
+    /// A local directory holding the included lists.
+    Directory(PathBuf),
+}
+
+impl<'source> FromPyObject<'source> for IncludeResolver {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        if ob.is_callable() {
+            Ok(Self::Callable(ob.into()))
+        } else {
+            Ok(Self::Directory(ob.extract()?))
+        }
+    }
+}
+
+impl IncludeResolver {
+    fn resolve(&self, py: Python<'_>, name: &str) -> PyResult<Option<String>> {
+        match self {
+            Self::Callable(callable) => callable.call1(py, (name,))?.extract(py),
+            Self::Directory(directory) => {
+                // Only allow lists inside the directory.
+                let path = Path::new(name);
+                let is_contained = path
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)));
+                if !is_contained {
+                    return Ok(None);
+                }
+                Ok(fs::read_to_string(directory.join(path)).ok())
+            }
+        }
+    }
+}
+
+/// Returns the name of the list that `name` refers to in an `!#include`
+/// directive of the list named `parent`. Names are relative to the including
+/// list, so `b.txt` included from `sub/a.txt` is `sub/b.txt`. Lists included
+/// from the added list, and URLs, keep their name.
+fn include_name(parent: Option<&str>, name: &str) -> String {
+    let directory = match parent.and_then(|parent| parent.rsplit_once('/')) {
+        Some((directory, _)) if !name.contains("://") && !name.starts_with('/') => directory,
+        _ => return name.to_string(),
+    };
+    let mut segments: Vec<&str> = directory.split('/').collect();
+    for segment in name.split('/') {
+        match segment {
+            "." => {}
+            ".." if matches!(segments.last(), Some(&last) if last != "..") => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Evaluates the condition of an `!#if` directive, such as
+/// `(env_chromium || env_edge) && !env_mobile`. Names are true if they are
+/// in `env`. Returns `None` if the condition isn't well formed.
+fn evaluate(condition: &str, env: &HashSet<String>) -> Option<bool> {
+    let mut parser = ConditionParser {
+        rest: condition,
+        env,
+    };
+    let value = parser.or()?;
+    if parser.rest.trim().is_empty() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+struct ConditionParser<'a> {
+    rest: &'a str,
+    env: &'a HashSet<String>,
+}
+
+impl ConditionParser<'_> {
+    fn eat(&mut self, token: &str) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn or(&mut self) -> Option<bool> {
+        let mut value = self.and()?;
+        while self.eat("||") {
+            // Always parse the right hand side, so errors are found.
+            value |= self.and()?;
+        }
+        Some(value)
+    }
+
+    fn and(&mut self) -> Option<bool> {
+        let mut value = self.unary()?;
+        while self.eat("&&") {
+            value &= self.unary()?;
+        }
+        Some(value)
+    }
+
+    fn unary(&mut self) -> Option<bool> {
+        if self.eat("!") {
+            return self.unary().map(|value| !value);
+        }
+        if self.eat("(") {
+            let value = self.or()?;
+            return if self.eat(")") { Some(value) } else { None };
+        }
+        self.rest = self.rest.trim_start();
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            return None;
+        }
+        let (name, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(self.env.contains(name))
+    }
+}
+
+/// Adds filter lists to a `FilterSet`, applying preprocessor directives.
+pub(crate) struct Preprocessor<'a> {
+    pub env: &'a HashSet<String>,
+    pub resolver: Option<&'a IncludeResolver>,
+    pub opts: ListOptions,
+    /// The `source` given when adding the list, also used for the filters of
+    /// included lists.
+    pub source: Option<&'a str>,
+    /// The names of the lists currently being included, used to detect
+    /// cycles.
+    pub include_stack: Vec<String>,
+}
+
+impl Preprocessor<'_> {
+    /// Adds the lines of `filter_list` to `filter_set`. `include` is the name
+    /// of the list if it was included by another one.
+    pub fn add_filter_list(
+        &mut self,
+        py: Python<'_>,
+        filter_set: &mut FilterSet,
+        report: &mut ParseReport,
+        filter_list: &str,
+        include: Option<&str>,
+    ) -> PyResult<()> {
+        // The value of the condition of each enclosing `!#if` block, negated
+        // after an `!#else`. Lines are only used if all of them are true.
+        let mut conditions: Vec<bool> = Vec::new();
+        let is_active = |conditions: &[bool]| conditions.iter().all(|&condition| condition);
+
+        for (index, line) in filter_list.lines().enumerate() {
+            let directive = line.trim();
+            if let Some(condition) = directive.strip_prefix("!#if ") {
+                conditions.push(evaluate(condition, self.env).unwrap_or(false));
+            } else if directive == "!#else" {
+                if let Some(condition) = conditions.last_mut() {
+                    *condition = !*condition;
+                }
+            } else if directive == "!#endif" {
+                conditions.pop();
+            } else if !is_active(&conditions) {
+                report.ignored += 1;
+                continue;
+            } else if let Some(name) = directive.strip_prefix("!#include ") {
+                let name = include_name(include, name.trim());
+                self.include(py, filter_set, report, &name)?;
+            } else {
+                filter_set.add_line(report, include, index + 1, line, self.opts, self.source);
+                continue;
+            }
+            // Directives are comments as far as the filter parser is
+            // concerned.
+            report.ignored += 1;
+        }
+        Ok(())
+    }
+
+    fn include(
+        &mut self,
+        py: Python<'_>,
+        filter_set: &mut FilterSet,
+        report: &mut ParseReport,
+        name: &str,
+    ) -> PyResult<()> {
+        if self.include_stack.iter().any(|included| included == name) {
+            report.include_cycles.push(name.to_string());
+            return Ok(());
+        }
+        let contents = match self.resolver {
+            Some(resolver) => resolver.resolve(py, name)?,
+            None => None,
+        };
+        let contents = match contents {
+            Some(contents) => contents,
+            None => {
+                report.unresolved_includes.push(name.to_string());
+                return Ok(());
+            }
+        };
+        self.include_stack.push(name.to_string());
+        let result = self.add_filter_list(py, filter_set, report, &contents, Some(name));
+        self.include_stack.pop();
+        result
+    }
+}
+
+/// Converts the `env` argument, which may be any iterable of strings. A
+/// plain string is rejected, since it would be iterated over per character.
+pub(crate) fn env_from_python(env: &PyAny) -> PyResult<HashSet<String>> {
+    if env.is_instance_of::<PyString>()? {
+        return Err(pyo3::exceptions::PyTypeError::new_err(
+            "env must be an iterable of flag names, not a string",
+        ));
+    }
+    env.iter()?.map(|flag| flag?.extract()).collect()
+}
@@ -0,0 +1,145 @@
+//! Diagnostics gathered while adding filters to a `FilterSet`.
+
+use adblock::filters::cosmetic::CosmeticFilterError;
+use adblock::filters::network::NetworkFilterError;
+use adblock::lists::FilterParseError;
+use pyo3::prelude::*;
+
+use crate::metadata::FilterListMetadata;
+use crate::DiyPythonRepr;
+
+/// Returns the type of filter (`"network"` or `"cosmetic"`) that failed to
+/// parse and the kind of error, or `None` if the line was not a filter rule
+/// at all, such as a comment or an empty line.
+pub(crate) fn parse_error_kind(error: &FilterParseError) -> Option<(&'static str, &'static str)> {
+    match error {
+        FilterParseError::Network(error) => Some(("network", network_error_kind(error))),
+        FilterParseError::Cosmetic(error) => Some(("cosmetic", cosmetic_error_kind(error))),
+        FilterParseError::Unsupported | FilterParseError::Empty => None,
+    }
+}
+
+fn network_error_kind(error: &NetworkFilterError) -> &'static str {
+    match error {
+        NetworkFilterError::FilterParseError => "filter_parse_error",
+        NetworkFilterError::BugValueNotNumeric => "bug_value_not_numeric",
+        NetworkFilterError::NegatedBadFilter => "negated_badfilter",
+        NetworkFilterError::NegatedImportant => "negated_important",
+        NetworkFilterError::NegatedOptionMatchCase => "negated_option_match_case",
+        NetworkFilterError::NegatedExplicitCancel => "negated_explicit_cancel",
+        NetworkFilterError::NegatedRedirection => "negated_redirection",
+        NetworkFilterError::NegatedTag => "negated_tag",
+        NetworkFilterError::NegatedGenericHide => "negated_generic_hide",
+        NetworkFilterError::NegatedDocument => "negated_document",
+        NetworkFilterError::GenericHideWithoutException => "generic_hide_without_exception",
+        NetworkFilterError::EmptyRedirection => "empty_redirection",
+        NetworkFilterError::RedirectionUrlInvalid => "redirection_url_invalid",
+        NetworkFilterError::MultipleRedirections => "multiple_redirections",
+        NetworkFilterError::UnrecognisedOption => "unrecognised_option",
+        NetworkFilterError::NoRegex => "no_regex",
+        NetworkFilterError::FullRegexUnsupported => "full_regex_unsupported",
+        NetworkFilterError::RegexParsingError(_) => "regex_parsing_error",
+        NetworkFilterError::PunycodeError => "punycode_error",
+        NetworkFilterError::CspWithContentType => "csp_with_content_type",
+    }
+}
+
+fn cosmetic_error_kind(error: &CosmeticFilterError) -> &'static str {
+    match error {
+        CosmeticFilterError::PunycodeError => "punycode_error",
+        CosmeticFilterError::InvalidStyleSpecifier => "invalid_style_specifier",
+        CosmeticFilterError::UnsupportedSyntax => "unsupported_syntax",
+        CosmeticFilterError::MissingSharp => "missing_sharp",
+        CosmeticFilterError::InvalidCssStyle => "invalid_css_style",
+        CosmeticFilterError::InvalidCssSelector => "invalid_css_selector",
+        CosmeticFilterError::GenericUnhide => "generic_unhide",
+        CosmeticFilterError::GenericScriptInject => "generic_script_inject",
+        CosmeticFilterError::GenericStyle => "generic_style",
+        CosmeticFilterError::DoubleNegation => "double_negation",
+        CosmeticFilterError::EmptyRule => "empty_rule",
+    }
+}
+
+/// A line that looked like a filter rule, but could not be parsed.
+#[pyclass]
+#[derive(Clone)]
+pub struct RejectedFilter {
+    /// The 1-based line number of the rule in the filter list, or its 1-based
+    /// position in the list given to `add_filters`.
+    #[pyo3(get)]
+    pub line_number: usize,
+    /// The name of the list the rule was included from with an `!#include`
+    /// directive, or `None` if it is part of the list that was added.
+    #[pyo3(get)]
+    pub include: Option<String>,
+    /// The text of the rule, as given.
+    #[pyo3(get)]
+    pub text: String,
+    /// Whether the rule was parsed as a `"network"` or a `"cosmetic"` filter.
+    #[pyo3(get)]
+    pub filter_type: &'static str,
+    /// The kind of parse error, such as `"unrecognised_option"`,
+    /// `"regex_parsing_error"` or `"invalid_css_selector"`, or
+    /// `"dns_only_option"` for AdGuard DNS filters that can't be converted.
+    #[pyo3(get)]
+    pub kind: &'static str,
+}
+
+#[pymethods]
+impl RejectedFilter {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "RejectedFilter(line_number={}, include={}, text={}, filter_type={}, kind={})",
+            self.line_number,
+            self.include.diy_python_repr(),
+            self.text.diy_python_repr(),
+            self.filter_type.diy_python_repr(),
+            self.kind.diy_python_repr(),
+        ))
+    }
+}
+
+/// A summary of the lines added to a `FilterSet`, returned by
+/// `FilterSet.add_filter_list` and `FilterSet.add_filters`.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct ParseReport {
+    /// The number of network filters added.
+    #[pyo3(get)]
+    pub network_filters: usize,
+    /// The number of cosmetic filters added.
+    #[pyo3(get)]
+    pub cosmetic_filters: usize,
+    /// The number of lines that are not filter rules, such as comments,
+    /// empty lines and preprocessor directives, or rules excluded by the
+    /// `rule_types` argument or an `!#if` condition.
+    #[pyo3(get)]
+    pub ignored: usize,
+    /// The lines that could not be parsed, in the order they were given.
+    #[pyo3(get)]
+    pub rejected: Vec<RejectedFilter>,
+    /// The names of the lists in `!#include` directives that couldn't be
+    /// resolved.
+    #[pyo3(get)]
+    pub unresolved_includes: Vec<String>,
+    /// The names of the lists in `!#include` directives that were skipped,
+    /// since they would include themselves.
+    #[pyo3(get)]
+    pub include_cycles: Vec<String>,
+    /// The metadata found in the header comments of the added lines.
+    #[pyo3(get)]
+    pub metadata: FilterListMetadata,
+}
+
+#[pymethods]
+impl ParseReport {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "ParseReport<network_filters={}, cosmetic_filters={}, ignored={}, rejected={}>",
+            self.network_filters,
+            self.cosmetic_filters,
+            self.ignored,
+            self.rejected.len(),
+        ))
+    }
+}
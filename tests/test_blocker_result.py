@@ -0,0 +1,109 @@
+import adblock
+import pickle
+
+
+def check(url, request_type="image"):
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filters(["||ads.example.com^", "@@||ads.example.com/ok/*"])
+    engine = adblock.Engine(filter_set)
+    return engine.check_network_urls(url, "https://news.com/", request_type)
+
+
+def test_equality():
+    result = check("https://ads.example.com/a.png")
+    assert result == check("https://ads.example.com/a.png")
+    assert result != check("https://ads.example.com/ok/a.png")
+    assert result != "BlockerResult"
+    assert result == adblock.BlockerResult(matched=True, filter="||ads.example.com^")
+
+
+def test_hash():
+    results = {
+        check("https://ads.example.com/a.png"),
+        check("https://ads.example.com/b.png"),
+        check("https://ads.example.com/ok/a.png"),
+    }
+    assert len(results) == 2
+    assert hash(adblock.BlockerResult()) == hash(adblock.BlockerResult())
+
+
+def test_constructor_defaults():
+    result = adblock.BlockerResult()
+    assert not result.matched
+    assert not result.important
+    assert result.redirect is None
+    assert result.redirect_type is None
+    assert result.redirect_resource_name is None
+    assert result.exception is None
+    assert result.filter is None
+    assert result.error is None
+    assert result.filter_source is None
+    assert result.exception_source is None
+
+
+def test_pickle():
+    result = check("https://ads.example.com/ok/a.png")
+    for protocol in range(pickle.HIGHEST_PROTOCOL + 1):
+        assert pickle.loads(pickle.dumps(result, protocol)) == result
+
+
+def test_to_dict():
+    result = check("https://ads.example.com/ok/a.png")
+    assert result.to_dict() == {
+        "matched": False,
+        "important": False,
+        "redirect": None,
+        "redirect_type": None,
+        "redirect_resource_name": None,
+        "exception": "@@||ads.example.com/ok/*",
+        "filter": "||ads.example.com^",
+        "error": None,
+        "filter_source": None,
+        "exception_source": None,
+    }
+    assert adblock.BlockerResult(**result.to_dict()) == result
+
+
+def test_match_args():
+    assert adblock.BlockerResult.__match_args__ == tuple(
+        adblock.BlockerResult().to_dict()
+    )
+
+
+def test_action():
+    assert check("https://example.org/a.png").action == adblock.BlockerAction.ALLOW
+    assert check("https://ads.example.com/a.png").action == adblock.BlockerAction.BLOCK
+    assert (
+        check("https://ads.example.com/ok/a.png").action
+        == adblock.BlockerAction.EXCEPTED
+    )
+    assert adblock.BlockerAction.EXCEPTED.value == "excepted"
+
+
+def test_action_precedence():
+    important = adblock.BlockerResult(
+        matched=True, important=True, exception=None, filter="||a.com^$important"
+    )
+    assert important.action == adblock.BlockerAction.BLOCK
+
+    redirect = adblock.BlockerResult(
+        matched=True, redirect="data:text/plain;base64,", redirect_type="resource"
+    )
+    assert redirect.action == adblock.BlockerAction.REDIRECT
+
+    # A `$redirect-rule` only applies to requests that are otherwise blocked.
+    redirect_rule = adblock.BlockerResult(
+        matched=False, redirect="data:text/plain;base64,", redirect_type="resource"
+    )
+    assert redirect_rule.action == adblock.BlockerAction.ALLOW
+
+
+def test_action_redirect():
+    filter_set = adblock.FilterSet()
+    filter_set.add_filters(["||ads.example.com^$redirect=noop.txt"])
+    engine = adblock.Engine(filter_set)
+    engine.add_resource(name="noop.txt", content_type="text/plain", content="")
+    result = engine.check_network_urls(
+        "https://ads.example.com/a.txt", "https://news.com/", "xmlhttprequest"
+    )
+    assert result.action == adblock.BlockerAction.REDIRECT
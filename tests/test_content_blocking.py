@@ -0,0 +1,73 @@
+import json
+
+import adblock
+import pytest
+
+FILTER_LIST = """\
+||ads.example.com^$third-party
+@@||ads.example.com/ok^
+||x.com^$redirect=noop.js
+/ba[rz]/
+||tracker.com^
+||tracker.com^$badfilter
+example.org##.banner
+example.org##+js(nobab)
+example.org##p:style(color: red)
+"""
+
+
+def get_rules():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(FILTER_LIST, source="easylist")
+    return filter_set.to_content_blocking()
+
+
+def test_rules():
+    rules = get_rules()
+    parsed = json.loads(rules.json)
+    assert len(parsed) == rules.rule_count
+    assert {
+        "action": {"type": "block"},
+        "trigger": {
+            "url-filter": "^[^:]+:(//)?([^/]+\\.)?ads\\.example\\.com",
+            "load-type": ["third-party"],
+        },
+    } in parsed
+    assert {
+        "action": {"type": "css-display-none", "selector": ".banner"},
+        "trigger": {"url-filter": ".*", "if-domain": ["example.org"]},
+    } in parsed
+
+    # Exceptions only apply to the rules before them.
+    types = [rule["action"]["type"] for rule in parsed]
+    assert types == ["block", "css-display-none"] + ["ignore-previous-rules"] * 2
+    assert repr(rules) == "ContentBlockingRules<rule_count=4, dropped=5>"
+
+
+def test_dropped():
+    dropped = get_rules().dropped
+    assert [(d.text, d.filter_type, d.kind, d.line_number) for d in dropped] == [
+        ("||x.com^$redirect=noop.js", "network", "redirect_unsupported", 3),
+        ("/ba[rz]/", "network", "regex_unsupported", 4),
+        ("||tracker.com^", "network", "badfiltered", 5),
+        ("example.org##+js(nobab)", "cosmetic", "scriptlet_unsupported", 8),
+        ("example.org##p:style(color: red)", "cosmetic", "style_unsupported", 9),
+    ]
+    assert all(d.source == "easylist" for d in dropped)
+    assert repr(dropped[0]) == (
+        "DroppedFilter(text='||x.com^$redirect=noop.js', filter_type='network', "
+        "kind='redirect_unsupported', line_number=3, source='easylist')"
+    )
+
+
+def test_empty():
+    rules = adblock.FilterSet().to_content_blocking()
+    assert json.loads(rules.json) == []
+    assert rules.dropped == []
+
+
+def test_requires_debug():
+    filter_set = adblock.FilterSet()
+    filter_set.add_filters(["||ads.example.com^"])
+    with pytest.raises(adblock.ConversionError):
+        filter_set.to_content_blocking()
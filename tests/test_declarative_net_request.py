@@ -0,0 +1,152 @@
+import json
+
+import adblock
+import pytest
+
+
+def convert(filters, **kwargs):
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filters(filters, source="easylist")
+    return filter_set.to_declarative_net_request(**kwargs)
+
+
+def rules(filters, **kwargs):
+    return json.loads(convert(filters, **kwargs).json)
+
+
+def test_block():
+    assert rules(["||ads.example.com^$third-party,script,domain=a.com|~b.a.com"]) == [
+        {
+            "id": 1,
+            "priority": 1,
+            "action": {"type": "block"},
+            "condition": {
+                "urlFilter": "||ads.example.com^",
+                "isUrlFilterCaseSensitive": False,
+                "initiatorDomains": ["a.com"],
+                "excludedInitiatorDomains": ["b.a.com"],
+                "domainType": "thirdParty",
+                "resourceTypes": ["script"],
+            },
+        }
+    ]
+
+
+@pytest.mark.parametrize(
+    "text, condition",
+    [
+        ("|https://a.com/ad.js|", {"urlFilter": "|https://a.com/ad.js|"}),
+        ("||A.com/Path$match-case", {"urlFilter": "||A.com/Path"}),
+        ("/ba[rz]/", {"regexFilter": "ba[rz]"}),
+        ("$image,1p,domain=a.com", {"initiatorDomains": ["a.com"]}),
+        ("||a.com^$document", {"resourceTypes": ["main_frame"]}),
+    ],
+)
+def test_condition(text, condition):
+    (rule,) = rules([text])
+    assert condition.items() <= rule["condition"].items()
+
+
+def test_exceptions():
+    converted = rules(["||a.com^", "@@||a.com/ok^", "@@||good.com^$document,image"])
+    assert [(r["id"], r["priority"], r["action"]["type"]) for r in converted] == [
+        (1, 2, "allow"),
+        (2, 2, "allowAllRequests"),
+        (3, 2, "allow"),
+        (4, 1, "block"),
+    ]
+    assert converted[1]["condition"]["resourceTypes"] == ["main_frame"]
+    assert converted[2]["condition"]["resourceTypes"] == ["image"]
+
+
+def test_exception_without_document_option():
+    # Like in the engine, the exception doesn't disable blocking on the pages
+    # it matches.
+    (exception,) = rules(["@@||a.com^"])
+    assert exception["action"] == {"type": "allow"}
+    assert "main_frame" in exception["condition"]["resourceTypes"]
+    (document,) = rules(["@@||a.com^$doc"])
+    assert document["action"] == {"type": "allowAllRequests"}
+
+
+def test_redirect_and_important():
+    redirect, important, upgrade = rules(
+        [
+            "||a.com/ad.js$script,redirect=noop.js",
+            "||b.com^$important",
+            "||c.com^$csp=upgrade-insecure-requests",
+        ],
+        resource_path="/resources/",
+    )
+    assert redirect["priority"] == 2
+    assert redirect["action"] == {
+        "type": "redirect",
+        "redirect": {"extensionPath": "/resources/noop.js"},
+    }
+    assert important["priority"] == 3
+    assert upgrade["action"] == {"type": "upgradeScheme"}
+
+
+def test_dropped():
+    converted = convert(
+        [
+            "||a.com^$redirect-rule=noop.js",
+            "/(?=x)y/",
+            "||tracker.com^",
+            "||tracker.com^$badfilter",
+            "||t.com^$tag=t",
+            "||c.com^$csp=script-src 'none'",
+            "ads$domain=example.*",
+            "@@||g.com^$generichide",
+        ]
+    )
+    assert json.loads(converted.json) == []
+    assert [(d.kind, d.line_number) for d in converted.dropped] == [
+        ("redirect_rule_unsupported", 1),
+        ("regex_unsupported", 2),
+        ("badfiltered", 3),
+        ("tag_unsupported", 5),
+        ("csp_unsupported", 6),
+        ("entities_unsupported", 7),
+        ("generichide_unsupported", 8),
+    ]
+    assert all(d.source == "easylist" for d in converted.dropped)
+
+
+@pytest.mark.parametrize("text", ["/$script", "@@/", "//"])
+def test_degenerate_regex(text):
+    converted = convert([text])
+    assert json.loads(converted.json) == []
+    assert [(d.text, d.kind) for d in converted.dropped] == [
+        (text, "regex_unsupported")
+    ]
+
+
+def test_limits():
+    filters = ["||a.com^", "/b[0-9]/", "/c[0-9]/", "@@||a.com/ok^"]
+    converted = convert(filters, max_rules=2, max_regex_rules=1)
+    assert [r["action"]["type"] for r in json.loads(converted.json)] == [
+        "allow",
+        "block",
+    ]
+    assert [(d.text, d.kind) for d in converted.dropped] == [
+        ("/b[0-9]/", "rule_limit_exceeded"),
+        ("/c[0-9]/", "rule_limit_exceeded"),
+    ]
+    assert repr(converted) == (
+        "DeclarativeNetRequestRules<rule_count=2, regex_rule_count=0, dropped=2>"
+    )
+
+    converted = convert(filters, max_rules=None, max_regex_rules=1)
+    assert converted.rule_count == 3
+    assert converted.regex_rule_count == 1
+    assert [(d.text, d.kind) for d in converted.dropped] == [
+        ("/c[0-9]/", "regex_limit_exceeded")
+    ]
+
+
+def test_requires_debug():
+    filter_set = adblock.FilterSet()
+    filter_set.add_filters(["||ads.example.com^"])
+    with pytest.raises(adblock.ConversionError):
+        filter_set.to_declarative_net_request()
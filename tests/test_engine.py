@@ -1,3 +1,5 @@
+import threading
+
 import adblock
 import pytest
 
@@ -72,3 +74,63 @@ def test_serde():
     engine2 = empty_engine()
     deserialization_result = engine2.deserialize(serialization_result)
     assert deserialization_result is None
+
+
+def test_check_network_urls_batch():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(SMALL_FILTER_LIST)
+    engine = adblock.Engine(filter_set=filter_set)
+
+    requests = [
+        ("https://wikipedia.org/img.png", "https://google.com/", "image"),
+        (
+            "https://facebook.com/directory/img.png",
+            "https://old.reddit.com/r/all",
+            "image",
+        ),
+        ("https://lobste.rs/s/abc", "https://lobste.rs/", "document"),
+        ("not a url", "https://lobste.rs/", "image"),
+    ]
+    results = engine.check_network_urls_batch(requests)
+    assert len(results) == len(requests)
+    for result, request in zip(results, requests):
+        single = engine.check_network_urls(*request)
+        assert result.matched == single.matched
+        assert result.filter == single.filter
+        assert result.error == single.error
+    assert [r.matched for r in results] == [True, False, True, False]
+
+    assert engine.check_network_urls_batch([]) == []
+
+
+def test_concurrent_queries_and_mutations():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(SMALL_FILTER_LIST + "||example.com^$tag=foo\n")
+    engine = adblock.Engine(filter_set=filter_set)
+    errors = []
+
+    def query():
+        try:
+            for _ in range(200):
+                result = engine.check_network_urls(
+                    "https://wikipedia.org/img.png", "https://google.com/", "image"
+                )
+                assert result.matched
+                engine.url_cosmetic_resources("https://wikipedia.org/")
+        except Exception as e:  # pylint: disable=broad-except
+            errors.append(e)
+
+    def mutate():
+        try:
+            for i in range(200):
+                engine.use_tags(["foo"] if i % 2 else [])
+        except Exception as e:  # pylint: disable=broad-except
+            errors.append(e)
+
+    threads = [threading.Thread(target=query) for _ in range(4)]
+    threads.append(threading.Thread(target=mutate))
+    for t in threads:
+        t.start()
+    for t in threads:
+        t.join()
+    assert errors == []
@@ -13,6 +13,11 @@ def test_correct_baseclasses():
     assert issubclass(adblock.OptimizedFilterExistence, adblock.BlockerException)
     assert issubclass(adblock.BadFilterAddUnsupported, adblock.BlockerException)
     assert issubclass(adblock.FilterExists, adblock.BlockerException)
+    assert issubclass(adblock.InvalidRequestError, adblock.AdblockException)
+    assert issubclass(adblock.InvalidRequestTypeError, adblock.AdblockException)
+    assert issubclass(adblock.InvalidUrlError, adblock.InvalidRequestError)
+    assert issubclass(adblock.InvalidRequestUrlError, adblock.InvalidUrlError)
+    assert issubclass(adblock.InvalidSourceUrlError, adblock.InvalidUrlError)
 
 
 def test_add_resource_error():
@@ -36,3 +41,76 @@ def test_add_resource_error():
             aliases=[],
         )
     assert "invalid utf content" in str(exc.value)
+
+
+@pytest.mark.parametrize(
+    "url, reason",
+    [
+        ("", "empty"),
+        ("example.com/ad.js", "missing_scheme"),
+        ("1http://example.com/", "missing_scheme"),
+        ("file:///etc/passwd", "unsupported_scheme"),
+        ("https://", "missing_host"),
+        ("https://?ad", "missing_host"),
+        ("https://user@:8080/", "missing_host"),
+        ("data:text/plain,hello", "missing_host"),
+        ("https://a\ufffdb.com/", "invalid_host"),
+    ],
+)
+def test_invalid_url_error(url, reason):
+    engine = adblock.Engine(adblock.FilterSet())
+
+    # By default, the error is only reported through `BlockerResult.error`.
+    result = engine.check_network_urls(url, "https://example.com/", "script")
+    assert result.error is not None
+    assert not result.matched
+
+    with pytest.raises(adblock.InvalidRequestUrlError) as exc:
+        engine.check_network_urls(url, "https://example.com/", "script", strict=True)
+    assert exc.value.url == url
+    assert exc.value.reason == reason
+
+    if url:
+        with pytest.raises(adblock.InvalidSourceUrlError) as exc:
+            engine.check_network_urls(
+                "https://example.com/", url, "script", strict=True
+            )
+        assert exc.value.reason == reason
+
+    with pytest.raises(adblock.InvalidRequestUrlError) as exc:
+        adblock.Request(url, "https://example.com/", "script")
+    assert exc.value.reason == reason
+
+
+def test_strict_with_hostnames():
+    engine = adblock.Engine(adblock.FilterSet())
+    # The hostnames are taken as given, only the request type is checked.
+    result = engine.check_network_urls_with_hostnames(
+        "not a url", "", "not a hostname", "script", strict=True
+    )
+    assert result.error is None
+    with pytest.raises(adblock.InvalidRequestTypeError):
+        engine.check_network_urls_with_hostnames(
+            "https://a.com/", "a.com", "b.com", "scripts", strict=True
+        )
+    with pytest.raises(adblock.InvalidRequestTypeError):
+        engine.check_network_urls_with_hostnames_subset(
+            "https://a.com/", "a.com", "b.com", "scripts", None, False, False, True
+        )
+
+
+def test_strict_source_url():
+    engine = adblock.Engine(adblock.FilterSet())
+    # Without a source URL, a request is still valid in strict mode.
+    result = engine.check_network_urls("https://a.com/", "", "script", strict=True)
+    assert result.error is None
+
+    # By default, an invalid source URL is ignored.
+    request = adblock.Request("https://a.com/", "not a url", "script")
+    assert request.source_hostname == ""
+    with pytest.raises(adblock.InvalidSourceUrlError):
+        adblock.Request("https://a.com/", "not a url", "script", strict=True)
+    with pytest.raises(adblock.InvalidSourceUrlError):
+        engine.check_network_urls_batch(
+            [("https://a.com/", "not a url", "script")], strict=True
+        )
@@ -0,0 +1,217 @@
+import adblock
+import pytest
+
+FILTER_LIST = """
+||ads.example.com^
+||ads.example.com^$image,important
+@@||ads.example.com/allowed/*
+||ads.example.com/tracked/*$tag=trackers
+||ads.example.com/old/*
+||ads.example.com/old/*$badfilter
+||ads.example.com/*.js$redirect-rule=noop.js
+@@||ads.example.com/*.js$redirect-rule=noop.js
+||unrelated.net^
+"""
+
+ALL_MATCHES_FILTER_LIST = """
+||ads.example.com^
+/banner/*
+||ads.example.com^$image,important
+@@||ads.example.com/allowed/*
+@@/allowed/*$image
+||ads.example.com/tracked/*$tag=trackers
+||ads.example.com/old/*
+||ads.example.com/old/*$badfilter
+||ads.example.com/*.js$redirect-rule=noop.js
+||ads.example.com^$csp=script-src 'none'
+@@||ads.example.com/allowed/*$csp
+"""
+
+SOURCE = "https://news.com/"
+
+
+def engine(filter_list=FILTER_LIST, debug=True, keep_filters=True):
+    filter_set = adblock.FilterSet(debug=debug)
+    filter_set.add_filter_list(filter_list)
+    return adblock.Engine(filter_set, keep_filters=keep_filters)
+
+
+def all_matches_engine(debug=True):
+    return engine(ALL_MATCHES_FILTER_LIST, debug=debug)
+
+
+def kinds(explanation):
+    return [(m.kind, m.filter) for m in explanation.matches]
+
+
+def texts(filters):
+    return sorted(f.filter for f in filters)
+
+
+def test_explain_blocking_and_exception():
+    explanation = engine().explain(
+        "https://ads.example.com/allowed/x.html", SOURCE, "subdocument"
+    )
+    assert isinstance(explanation, adblock.Explanation)
+    assert kinds(explanation) == [
+        ("block", "||ads.example.com^"),
+        ("exception", "@@||ads.example.com/allowed/*"),
+    ]
+    assert not explanation.result.matched
+    assert explanation.result.exception == "@@||ads.example.com/allowed/*"
+
+
+def test_explain_important():
+    explanation = engine().explain(
+        "https://ads.example.com/allowed/x.png", SOURCE, "image"
+    )
+    assert kinds(explanation) == [
+        ("important", "||ads.example.com^$image,important"),
+        ("block", "||ads.example.com^"),
+        ("exception", "@@||ads.example.com/allowed/*"),
+    ]
+    assert explanation.result.matched
+    assert explanation.result.important
+
+
+def test_explain_badfilter():
+    explanation = engine().explain(
+        "https://ads.example.com/old/x.html", SOURCE, "subdocument"
+    )
+    assert explanation.matches[0].kind == "badfilter"
+    assert explanation.matches[0].filter == "||ads.example.com/old/*"
+    assert explanation.matches[0].cancelled_by == "||ads.example.com/old/*$badfilter"
+    assert kinds(explanation)[1:] == [("block", "||ads.example.com^")]
+
+
+def test_explain_tags():
+    e = engine()
+    url = "https://ads.example.com/tracked/x.html"
+    explanation = e.explain(url, SOURCE, "subdocument")
+    assert kinds(explanation) == [
+        ("tag_disabled", "||ads.example.com/tracked/*$tag=trackers"),
+        ("block", "||ads.example.com^"),
+    ]
+    assert explanation.matches[0].tag == "trackers"
+
+    e.enable_tags(["trackers"])
+    explanation = e.explain(url, SOURCE, "subdocument")
+    assert kinds(explanation)[0] == (
+        "block",
+        "||ads.example.com/tracked/*$tag=trackers",
+    )
+
+
+def test_explain_redirects():
+    explanation = engine().explain("https://ads.example.com/a.js", SOURCE, "script")
+    assert kinds(explanation) == [
+        ("block", "||ads.example.com^"),
+        ("exception", "@@||ads.example.com/*.js$redirect-rule=noop.js"),
+        ("redirect", "||ads.example.com/*.js$redirect-rule=noop.js"),
+        ("redirect_exception", "@@||ads.example.com/*.js$redirect-rule=noop.js"),
+    ]
+    assert explanation.result.redirect is None
+
+
+def test_explain_without_debug():
+    explanation = engine(debug=False).explain(
+        "https://ads.example.com/allowed/x.html", SOURCE, "subdocument"
+    )
+    assert [m.kind for m in explanation.matches] == ["block", "exception"]
+    assert all(m.filter is None for m in explanation.matches)
+
+
+def test_explain_no_match():
+    explanation = engine().explain("https://example.org/", SOURCE, "document")
+    assert explanation.matches == []
+    assert not explanation.result.matched
+
+
+def test_explain_invalid_url():
+    with pytest.raises(adblock.InvalidRequestUrlError):
+        engine().explain("not a url", SOURCE, "image")
+
+
+@pytest.mark.parametrize("method", ["explain", "check_network_urls_all_matches"])
+def test_deserialized(method):
+    e = engine()
+    e.deserialize(engine().serialize())
+    with pytest.raises(adblock.AdblockException, match="deserialize"):
+        getattr(e, method)("https://ads.example.com/", SOURCE, "image")
+
+
+@pytest.mark.parametrize("method", ["explain", "check_network_urls_all_matches"])
+def test_without_keep_filters(method):
+    e = engine(keep_filters=False)
+    with pytest.raises(adblock.AdblockException, match="keep_filters=True"):
+        getattr(e, method)("https://ads.example.com/", SOURCE, "image")
+
+
+def test_all_matches_blocking_and_exceptions():
+    matches = all_matches_engine().check_network_urls_all_matches(
+        "https://ads.example.com/allowed/banner/x.png", SOURCE, "image"
+    )
+    assert isinstance(matches, adblock.FilterMatches)
+    assert texts(matches.block) == ["/banner/*", "||ads.example.com^"]
+    assert texts(matches.exception) == [
+        "@@/allowed/*$image",
+        "@@||ads.example.com/allowed/*",
+    ]
+    assert texts(matches.important) == ["||ads.example.com^$image,important"]
+    assert matches.redirect == []
+    assert matches.csp == []
+
+
+def test_all_matches_skips_cancelled_and_tagged():
+    e = all_matches_engine()
+    matches = e.check_network_urls_all_matches(
+        "https://ads.example.com/old/tracked/x.html", SOURCE, "xmlhttprequest"
+    )
+    assert texts(matches.block) == ["||ads.example.com^"]
+
+    e.enable_tags(["trackers"])
+    matches = e.check_network_urls_all_matches(
+        "https://ads.example.com/tracked/x.html", SOURCE, "xmlhttprequest"
+    )
+    assert texts(matches.block) == [
+        "||ads.example.com/tracked/*$tag=trackers",
+        "||ads.example.com^",
+    ]
+
+
+def test_all_matches_redirect():
+    matches = all_matches_engine().check_network_urls_all_matches(
+        "https://ads.example.com/a.js", SOURCE, "script"
+    )
+    assert texts(matches.redirect) == ["||ads.example.com/*.js$redirect-rule=noop.js"]
+    assert matches.redirect[0].kind == "redirect"
+
+
+def test_all_matches_csp():
+    e = all_matches_engine()
+    matches = e.check_network_urls_all_matches(
+        "https://ads.example.com/", SOURCE, "document"
+    )
+    assert texts(matches.csp) == ["||ads.example.com^$csp=script-src 'none'"]
+    assert matches.csp[0].kind == "csp"
+
+    matches = e.check_network_urls_all_matches(
+        "https://ads.example.com/allowed/", SOURCE, "document"
+    )
+    assert "@@||ads.example.com/allowed/*$csp" in texts(matches.exception)
+
+
+def test_all_matches_without_debug():
+    matches = all_matches_engine(debug=False).check_network_urls_all_matches(
+        "https://ads.example.com/banner/x.png", SOURCE, "image"
+    )
+    assert len(matches.block) == 2
+    assert all(f.filter is None for f in matches.block)
+
+
+def test_all_matches_invalid_url():
+    e = all_matches_engine()
+    matches = e.check_network_urls_all_matches("not a url", SOURCE, "image")
+    assert matches.block == []
+    with pytest.raises(adblock.InvalidRequestUrlError):
+        e.check_network_urls_all_matches("not a url", SOURCE, "image", strict=True)
@@ -0,0 +1,65 @@
+import adblock
+import gzip
+import pathlib
+import pytest
+
+FILTER_LIST = "! Title: File list\n||ads.example.com^\nexample.com##.ad\n||bad^$nope\n"
+
+
+def check(filter_set, url="https://ads.example.com/a.png"):
+    engine = adblock.Engine(filter_set)
+    return engine.check_network_urls(url, "https://news.com/", "image").matched
+
+
+def test_from_file(tmpdir):
+    path = pathlib.Path(str(tmpdir)) / "list.txt"
+    path.write_bytes(FILTER_LIST.encode())
+
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filter_list_from_file(path)
+    assert report.network_filters == 1
+    assert report.cosmetic_filters == 1
+    assert report.metadata.title == "File list"
+    assert [(r.line_number, r.text) for r in report.rejected] == [(4, "||bad^$nope")]
+    assert check(filter_set)
+
+    # Plain strings are accepted too.
+    filter_set = adblock.FilterSet()
+    filter_set.add_filter_list_from_file(str(path))
+    assert check(filter_set)
+
+
+def test_from_file_bom_and_crlf(tmpdir):
+    path = pathlib.Path(str(tmpdir)) / "list.txt"
+    content = FILTER_LIST.replace("\n", "\r\n")
+    path.write_bytes(b"\xef\xbb\xbf" + content.encode())
+
+    filter_set = adblock.FilterSet(debug=True)
+    report = filter_set.add_filter_list_from_file(path)
+    assert report.metadata.title == "File list"
+    assert report.rejected[0].text == "||bad^$nope"
+    assert check(filter_set)
+
+
+def test_from_gzip_file(tmpdir):
+    path = pathlib.Path(str(tmpdir)) / "list.txt.gz"
+    path.write_bytes(gzip.compress(FILTER_LIST.encode()))
+
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filter_list_from_file(path)
+    assert report.network_filters == 1
+    assert check(filter_set)
+
+
+def test_from_file_hosts(tmpdir):
+    path = pathlib.Path(str(tmpdir)) / "hosts"
+    path.write_bytes(b"0.0.0.0 ads.example.com\n")
+
+    filter_set = adblock.FilterSet()
+    filter_set.add_filter_list_from_file(path, format="hosts")
+    assert check(filter_set)
+
+
+def test_from_missing_file(tmpdir):
+    with pytest.raises(FileNotFoundError):
+        adblock.FilterSet().add_filter_list_from_file(str(tmpdir) + "/missing.txt")
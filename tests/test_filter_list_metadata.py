@@ -0,0 +1,67 @@
+import adblock
+from datetime import timedelta
+
+HEADER = """[Adblock Plus 2.0]
+! Version: 202210181200
+! Title: EasyList
+! Last modified: 18 Oct 2022 12:00 UTC
+! Expires: 4 days (update frequency)
+! Homepage: https://easylist.to/
+! Title: Not the title
+||ads.example.com^
+"""
+
+
+def test_metadata():
+    report = adblock.FilterSet().add_filter_list(HEADER)
+    metadata = report.metadata
+    assert isinstance(metadata, adblock.FilterListMetadata)
+    assert metadata.title == "EasyList"
+    assert metadata.homepage == "https://easylist.to/"
+    assert metadata.version == "202210181200"
+    assert metadata.last_modified == "18 Oct 2022 12:00 UTC"
+    assert metadata.redirect is None
+    assert metadata.expires == timedelta(days=4)
+    assert report.network_filters == 1
+
+
+def test_metadata_missing():
+    metadata = adblock.FilterSet().add_filter_list("||ads.example.com^").metadata
+    assert metadata.title is None
+    assert metadata.expires is None
+
+
+def test_expires():
+    def expires(value):
+        report = adblock.FilterSet().add_filters(["! Expires: " + value])
+        return report.metadata.expires
+
+    assert expires("12 hours") == timedelta(hours=12)
+    assert expires("1 hour") == timedelta(hours=1)
+    assert expires("1 day") == timedelta(days=1)
+    assert expires("0 days") is None
+    assert expires("soon") is None
+    assert expires("3 weeks") is None
+    assert expires("999999999 days") == timedelta(days=999999999)
+    assert expires("1000000000 days") is None
+    assert expires("99999999999 days") is None
+    assert expires("99999999999999999999 days") is None
+
+
+def test_metadata_ends_at_first_rule():
+    report = adblock.FilterSet().add_filter_list(
+        "! Version: 1\n\n||ads.example.com^\n! Title: Section title\n"
+        "! Expires: 1 day\n"
+    )
+    assert report.metadata.version == "1"
+    assert report.metadata.title is None
+    assert report.metadata.expires is None
+
+
+def test_metadata_repr():
+    metadata = adblock.FilterSet().add_filter_list(HEADER).metadata
+    assert repr(metadata) == (
+        "FilterListMetadata(title='EasyList', homepage='https://easylist.to/', "
+        "version='202210181200', last_modified='18 Oct 2022 12:00 UTC', "
+        "redirect=None, expires=datetime.timedelta(days=4))"
+    )
@@ -0,0 +1,188 @@
+import adblock
+import copy
+import pickle
+import pytest
+
+FILTER_LIST = """! Comment
+||ads.example.com^
+||tracker.example.com^$third-party
+@@||ads.example.com/ok/*
+||ads.example.com/*.js$redirect=noop.js
+example.com##.ad
+example.com##+js(nowebrtc)
+example.com#@#.sponsored
+||bad.example.com^$nope
+"""
+
+
+def test_len():
+    filter_set = adblock.FilterSet()
+    assert len(filter_set) == 0
+    filter_set.add_filter_list(FILTER_LIST)
+    assert len(filter_set) == 7
+    filter_set.add_filters(["||more.example.com^"])
+    assert len(filter_set) == 8
+
+
+def test_stats():
+    filter_set = adblock.FilterSet()
+    filter_set.add_filter_list(FILTER_LIST)
+    stats = filter_set.stats()
+    assert isinstance(stats, adblock.FilterSetStats)
+    assert stats.network_filters == 4
+    assert stats.exception_filters == 1
+    assert stats.redirect_filters == 1
+    assert stats.cosmetic_filters == 3
+    assert stats.scriptlet_filters == 1
+
+    other = adblock.FilterSet()
+    other.add_filters(["||ads.example.com^", "@@||other.example.com^", "a.com##+js(x)"])
+    stats = (filter_set | other).stats()
+    assert stats.network_filters == 5
+    assert stats.exception_filters == 2
+    assert stats.scriptlet_filters == 2
+
+
+def test_iter_debug():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(FILTER_LIST)
+    assert list(filter_set) == [
+        "||ads.example.com^",
+        "||tracker.example.com^$third-party",
+        "@@||ads.example.com/ok/*",
+        "||ads.example.com/*.js$redirect=noop.js",
+        "example.com##.ad",
+        "example.com##+js(nowebrtc)",
+        "example.com#@#.sponsored",
+    ]
+
+
+def test_iter_without_debug():
+    filter_set = adblock.FilterSet()
+    filter_set.add_filter_list(FILTER_LIST)
+    with pytest.raises(adblock.AdblockException):
+        iter(filter_set)
+
+
+def test_engine_uses_cosmetic_filters():
+    filter_set = adblock.FilterSet()
+    filter_set.add_filter_list(FILTER_LIST)
+    engine = adblock.Engine(filter_set)
+    resources = engine.url_cosmetic_resources("https://example.com/")
+    assert ".ad" in resources.hide_selectors
+    assert ".sponsored" in resources.exceptions
+
+
+def make(filters, debug=True):
+    filter_set = adblock.FilterSet(debug=debug)
+    filter_set.add_filters(filters)
+    return filter_set
+
+
+def test_merge():
+    base = make(["||ads.example.com^", "example.com##.ad"])
+    extra = make(["||ads.example.com^", "||customer.example^", "example.com##.ad"])
+    base.merge(extra)
+    assert list(base) == [
+        "||ads.example.com^",
+        "||customer.example^",
+        "example.com##.ad",
+    ]
+    assert len(extra) == 3
+
+
+def test_merge_keeps_distinct_options():
+    filter_set = make(
+        [
+            "||ads.example.com^$redirect=noop.js",
+            "||ads.example.com^$redirect=noop.txt",
+            "||ads.example.com^$tag=a",
+            "||ads.example.com^$tag=b",
+        ]
+    )
+    assert len(filter_set | filter_set) == 4
+
+
+def test_union_operators():
+    base = make(["||ads.example.com^"])
+    extra = make(["||customer.example^"])
+
+    union = base | extra
+    assert len(union) == 2
+    assert len(base) == 1
+
+    original = base
+    base |= extra
+    assert base is original
+    assert len(base) == 2
+
+    with pytest.raises(TypeError):
+        base | ["||other.example^"]
+
+
+def test_copy():
+    base = make(["||ads.example.com^"])
+    variant = base.copy()
+    variant.add_filters(["||customer.example^"])
+    assert len(base) == 1
+    assert len(variant) == 2
+    assert len(copy.copy(base)) == 1
+
+
+def test_merge_debug_rule():
+    debug = make(["||ads.example.com^"])
+    no_debug = make(["||customer.example^"], debug=False)
+
+    for union in (debug | no_debug, no_debug | debug):
+        assert repr(union) == "FilterSet(debug=False)"
+        assert len(union) == 2
+        with pytest.raises(adblock.AdblockException):
+            iter(union)
+
+    assert repr(debug | debug.copy()) == "FilterSet(debug=True)"
+
+
+def test_merged_engine():
+    union = make(["||ads.example.com^"]) | make(["@@||ads.example.com/ok/*"])
+    engine = adblock.Engine(union)
+    result = engine.check_network_urls(
+        "https://ads.example.com/ok/a.png", "https://news.com/", "image"
+    )
+    assert result.filter == "||ads.example.com^"
+    assert result.exception == "@@||ads.example.com/ok/*"
+
+
+def test_serialize():
+    original = make(FILTER_LIST.splitlines())
+    restored = adblock.FilterSet()
+    restored.deserialize(original.serialize())
+    assert repr(restored) == "FilterSet(debug=True)"
+    assert list(restored) == list(original)
+
+    engine = adblock.Engine(restored, optimize=False)
+    result = engine.check_network_urls(
+        "https://ads.example.com/ok/a.png", "https://news.com/", "image"
+    )
+    assert result.exception == "@@||ads.example.com/ok/*"
+    resources = engine.url_cosmetic_resources("https://example.com/")
+    assert ".ad" in resources.hide_selectors
+
+
+def test_deserialize_invalid():
+    filter_set = adblock.FilterSet()
+    with pytest.raises(adblock.DeserializationError):
+        filter_set.deserialize(b"not a filter set")
+
+    engine = adblock.Engine(make(["||ads.example.com^"]))
+    with pytest.raises(adblock.DeserializationError):
+        filter_set.deserialize(engine.serialize())
+
+
+def test_pickle():
+    for debug in (True, False):
+        original = make(FILTER_LIST.splitlines(), debug=debug)
+        restored = pickle.loads(pickle.dumps(original))
+        assert repr(restored) == repr(original)
+        assert restored.stats().network_filters == 4
+        assert restored.stats().cosmetic_filters == 3
+    assert len(copy.deepcopy(original)) == len(original)
@@ -0,0 +1,131 @@
+import adblock
+import pathlib
+import pytest
+
+
+def blocks(filter_set, url):
+    engine = adblock.Engine(filter_set)
+    return engine.check_network_urls(url, "https://news.com/", "script").matched
+
+
+def test_domains():
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filter_list(
+        "# Tracking domains\nads.example.com\n*.tracker.example.net # wildcard\n",
+        format="domains",
+    )
+    assert report.network_filters == 2
+    assert report.ignored == 1
+    assert blocks(filter_set, "https://ads.example.com/a.js")
+    assert blocks(filter_set, "https://cdn.tracker.example.net/a.js")
+    assert not blocks(filter_set, "https://tracker.example.net/a.js")
+    assert not blocks(filter_set, "https://example.com/a.js")
+
+
+def test_dnsmasq():
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filter_list(
+        "\n".join(
+            [
+                "# dnsmasq blocklist",
+                "address=/ads.example.com/0.0.0.0",
+                "address=/a.example.org/b.example.org/",
+                "server=/tracker.example.net/",
+                "server=/forwarded.example.net/1.1.1.1",
+                "address=/printer.lan/192.168.1.10",
+                "cache-size=1000",
+            ]
+        ),
+        format="dnsmasq",
+    )
+    assert report.network_filters == 4
+    assert report.ignored == 4
+    assert blocks(filter_set, "https://ads.example.com/a.js")
+    assert blocks(filter_set, "https://b.example.org/a.js")
+    assert blocks(filter_set, "https://tracker.example.net/a.js")
+    assert not blocks(filter_set, "https://forwarded.example.net/a.js")
+    assert not blocks(filter_set, "https://printer.lan/a.js")
+
+
+def test_dnsmasq_rejected():
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filter_list("address=/com/0.0.0.0", format="dnsmasq")
+    assert report.network_filters == 0
+    assert [r.text for r in report.rejected] == ["address=/com/0.0.0.0"]
+
+
+def test_adguard_dns():
+    filter_set = adblock.FilterSet(debug=True)
+    report = filter_set.add_filter_list(
+        "\n".join(
+            [
+                "! AdGuard DNS filter",
+                "||ads.example.com^$important,client=192.168.0.1",
+                "||tracker.example.com^$dnstype=AAAA",
+                "0.0.0.0 hosts.example.com",
+                "plain.example.com",
+                "@@||ok.ads.example.com^",
+                "||rewritten.example.com^$dnsrewrite=1.2.3.4",
+            ]
+        ),
+        format="adguard_dns",
+    )
+    assert report.network_filters == 3
+    assert report.ignored == 2
+    assert [(r.line_number, r.kind) for r in report.rejected] == [
+        (2, "dns_only_option"),
+        (3, "dns_only_option"),
+    ]
+    assert not blocks(filter_set, "https://ads.example.com/a.js")
+    assert not blocks(filter_set, "https://tracker.example.com/a.js")
+    assert blocks(filter_set, "https://hosts.example.com/a.js")
+    assert blocks(filter_set, "https://plain.example.com/a.js")
+    assert not blocks(filter_set, "https://rewritten.example.com/a.js")
+
+
+@pytest.mark.parametrize(
+    "filter_list, url",
+    [
+        ("[Adblock Plus 2.0]\n||ads.example.com^", "https://ads.example.com/"),
+        ("127.0.0.1 localhost\n0.0.0.0 ads.example.com", "https://ads.example.com/"),
+        ("# list\nads.example.com\n", "https://ads.example.com/"),
+        ("address=/ads.example.com/0.0.0.0", "https://ads.example.com/"),
+        (
+            "||x.example.com^$dnsrewrite=1.2.3.4\n||ads.example.com^",
+            "https://ads.example.com/",
+        ),
+    ],
+)
+def test_auto(filter_list, url):
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filter_list(filter_list, format="auto")
+    assert report.rejected == []
+    assert blocks(filter_set, url)
+
+
+def test_auto_add_filters():
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filters(["ads.example.com", "*.example.net"], "auto")
+    assert report.network_filters == 2
+    assert blocks(filter_set, "https://cdn.example.net/a.js")
+
+
+def test_auto_from_file(tmpdir):
+    path = pathlib.Path(str(tmpdir)) / "list.conf"
+    path.write_text("address=/ads.example.com/0.0.0.0\n")
+    filter_set = adblock.FilterSet()
+    filter_set.add_filter_list_from_file(path, format="auto")
+    assert blocks(filter_set, "https://ads.example.com/a.js")
+
+
+def test_invalid_format():
+    with pytest.raises(adblock.AdblockException):
+        adblock.FilterSet().add_filter_list("", format="bind")
+
+
+def test_adguard_dns_wildcard_domain():
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filters(["*.example.net"], format="adguard_dns")
+    assert report.network_filters == 1
+    assert blocks(filter_set, "https://cdn.example.net/a.js")
+    assert not blocks(filter_set, "https://example.net/a.js")
@@ -0,0 +1,135 @@
+import adblock
+import pytest
+
+
+def lint(filters, debug=True):
+    filter_set = adblock.FilterSet(debug=debug)
+    filter_set.add_filter_list("\n".join(filters), source="easylist")
+    return [(f.kind, f.line_number) for f in filter_set.lint()]
+
+
+def test_clean_list():
+    assert lint(["||ads.example.com^", "example.org##.banner"]) == []
+
+
+def test_duplicates():
+    findings = lint(
+        [
+            "||ads.example.com^$image,script",
+            "||ads.example.com^$image,script",
+            "||ads.example.com^$script,image",
+            "example.org##.banner",
+            "example.org##.banner",
+            "example.org,example.net##.popup",
+            "example.net,example.org##.popup",
+        ]
+    )
+    assert findings == [
+        ("duplicate", 2),
+        ("semantic_duplicate", 3),
+        ("duplicate", 5),
+        ("semantic_duplicate", 7),
+    ]
+
+
+def test_duplicates_without_debug():
+    findings = lint(["||ads.example.com^", "||ads.example.com^"], debug=False)
+    assert findings == [("duplicate", 2)]
+
+
+def test_finding_fields():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(
+        "! Title: List\n||ads.example.com^\n||ads.example.com^\n", source="easylist"
+    )
+    (finding,) = filter_set.lint()
+    assert finding.kind == "duplicate"
+    assert finding.line_number == 3
+    assert finding.include is None
+    assert finding.source == "easylist"
+    assert finding.text == "||ads.example.com^"
+    assert "line 2" in finding.message
+    assert "LintFinding(kind='duplicate'" in repr(finding)
+
+
+def test_badfiltered():
+    findings = lint(["||tracker.com^", "||tracker.com^$badfilter"])
+    assert findings == [("badfiltered", 1)]
+
+
+def test_unused_exception():
+    findings = lint(
+        [
+            "||ads.example.com^",
+            "@@||ads.example.com/ok/*",
+            "@@||nothing.example.net^",
+        ]
+    )
+    assert findings == [("unused_exception", 3)]
+
+
+def test_exception_for_domain_restricted_filter():
+    findings = lint(
+        [
+            "||ads.example.com^$domain=news.com",
+            "||tracker.example.net^$domain=~news.com",
+            "@@||ads.example.com/ok/*",
+            "@@||tracker.example.net/ok/*",
+        ]
+    )
+    assert findings == []
+
+
+@pytest.mark.parametrize("rule", ["ads", "/ad^", "-ad-"])
+def test_broad_pattern(rule):
+    assert lint([rule]) == [("broad_pattern", 1)]
+
+
+@pytest.mark.parametrize(
+    "rule", ["/ads/banner", "||ad.com^", "ads$domain=a.com", "$third-party,script"]
+)
+def test_not_broad_pattern(rule):
+    assert lint([rule]) == []
+
+
+def test_slow_regex():
+    assert lint(["/ba[rz]/"]) == [("slow_regex", 1)]
+
+
+@pytest.mark.parametrize(
+    "selector",
+    ["div[", "a >", "> a", "a,,b", "a.", "#1a", "div {", "a)", 'div"x"'],
+)
+def test_invalid_selector(selector):
+    assert lint(["example.org##" + selector]) == [("invalid_selector", 1)]
+
+
+@pytest.mark.parametrize(
+    "selector",
+    [
+        "div > a + b ~ c",
+        ".ad, #banner",
+        'a[href^="https://[ad]"]',
+        "div:has(> .ad)",
+        "p::before",
+        ":-abp-contains(ad)",
+    ],
+)
+def test_valid_selector(selector):
+    assert lint(["example.org##" + selector]) == []
+
+
+def test_scriptlets_are_not_selectors():
+    assert lint(["example.org##+js(nobab)"]) == []
+
+
+def test_include_reference():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(
+        "||ads.example.com^\n!#include other.txt\n",
+        include_resolver=lambda name: "||ads.example.com^\n",
+    )
+    (finding,) = filter_set.lint()
+    assert finding.include == "other.txt"
+    assert finding.line_number == 1
+    assert finding.message == "same as the filter on line 1"
@@ -0,0 +1,96 @@
+import adblock
+import pytest
+
+RULES = [
+    "||ads.example.com^$3p,xhr",
+    "@@||a.com/x^$~script,~css,1p,domain=b.com|a.com|~c.a.com,important",
+    "/Ba[rz]/$csp=script-src 'none'",
+    "ads$redirect=noop.js,tag=t,~image",
+    "ads$redirect-rule=noop.js",
+    "||x.com^$badfilter",
+    "@@||x.com^$ghide",
+    "|http://x.com/y|$image,frame,doc",
+    "ads$domain=a.com,domain=~b.com",
+    "ads$bug=12,collapse",
+    "||A.com/Path$match-case",
+    "b.com,a.com,~c.a.com##div.ad",
+    "a.com#@#.x",
+    'a.com##+js(set-constant,a\\, b ,  "c")',
+    "a.com##p:style(color: red)",
+]
+
+
+@pytest.mark.parametrize(
+    "text, canonical",
+    [
+        ("||Ads.example.com^$3p,xhr", "||ads.example.com^$xmlhttprequest,third-party"),
+        (
+            "@@||a.com/x^$~script,~css,1p,domain=b.com|a.com|~c.a.com,important",
+            "@@||a.com/x^$~script,~stylesheet,first-party,"
+            "domain=a.com|b.com|~c.a.com,important",
+        ),
+        ("ads$redirect=noop.js,tag=t,~image", "ads$~image,redirect=noop.js,tag=t"),
+        ("@@||x.com^$ghide", "@@||x.com^$generichide"),
+        ("|http://x.com/y|$image,frame,doc", "|http://x.com/y|$document,image,subdocument"),
+        ("ads$domain=a.com,domain=~b.com", "ads$domain=a.com|~b.com"),
+        ("||A.com/Path$match-case", "||A.com/Path$match-case"),
+        ("/Ba[rz]/", "/Ba[rz]/"),
+        ("b.com,a.com,~c.a.com##div.ad", "a.com,b.com,~c.a.com##div.ad"),
+        ('a.com##+js(set-constant,a\\, b ,  "c")', 'a.com##+js(set-constant, a\\, b, "c")'),
+        ("  a.com##p:style(color: red)  ", "a.com##p:style(color: red)"),
+    ],
+)
+def test_normalize_filter(text, canonical):
+    assert adblock.normalize_filter(text) == canonical
+
+
+@pytest.mark.parametrize("text", RULES)
+def test_idempotent(text):
+    canonical = adblock.normalize_filter(text)
+    assert adblock.normalize_filter(canonical) == canonical
+
+
+@pytest.mark.parametrize("text", RULES)
+def test_parse_equivalent(text):
+    # `lint` reports filters with the same meaning as duplicates.
+    canonical = adblock.normalize_filter(text)
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filters([text, canonical])
+    kinds = {"duplicate", "semantic_duplicate"}
+    assert [f.kind for f in filter_set.lint() if f.kind in kinds] == [
+        "duplicate" if canonical == text else "semantic_duplicate"
+    ]
+
+
+def test_normalize_filter_error():
+    with pytest.raises(adblock.FilterParseError) as exc:
+        adblock.normalize_filter("x$foo")
+    assert exc.value.kind == "unrecognised_option"
+
+
+def test_canonicalize():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(
+        "||Ads.example.com^$xhr,3p\nb.com,a.com##.ad\n", canonicalize=True
+    )
+    filter_set.add_filters(["0.0.0.0 Tracker.com"], format="hosts", canonicalize=True)
+    assert list(filter_set) == [
+        "||ads.example.com^$xmlhttprequest,third-party",
+        "||tracker.com^",
+        "a.com,b.com##.ad",
+    ]
+
+    engine = adblock.Engine(filter_set)
+    result = engine.check_network_urls(
+        "https://ads.example.com/a", "https://news.com/", "xmlhttprequest"
+    )
+    assert result.matched
+    assert result.filter == "||ads.example.com^$xmlhttprequest,third-party"
+    resources = engine.url_cosmetic_resources("https://a.com/")
+    assert ".ad" in resources.hide_selectors
+
+
+def test_canonicalize_default():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filters(["||Ads.example.com^$xhr,3p"])
+    assert list(filter_set) == ["||Ads.example.com^$xhr,3p"]
@@ -0,0 +1,63 @@
+import adblock
+import pytest
+
+
+def test_filter_format_values():
+    assert adblock.FilterFormat.STANDARD.value == "standard"
+    assert adblock.FilterFormat.HOSTS.value == "hosts"
+    assert adblock.FilterFormat.ADGUARD_DNS.value == "adguard_dns"
+    assert adblock.FilterFormat.AUTO.value == "auto"
+    assert adblock.FilterFormat.HOSTS == adblock.FilterFormat.HOSTS
+    assert adblock.FilterFormat.HOSTS != adblock.FilterFormat.DOMAINS
+
+
+def test_rule_types_values():
+    assert adblock.RuleTypes.ALL.value == "all"
+    assert adblock.RuleTypes.NETWORK_ONLY.value == "networkonly"
+    assert adblock.RuleTypes.COSMETIC_ONLY.value == "cosmeticonly"
+
+
+def test_enums_accepted():
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filter_list(
+        "0.0.0.0 ads.example.com",
+        format=adblock.FilterFormat.HOSTS,
+        rule_types=adblock.RuleTypes.NETWORK_ONLY,
+    )
+    assert report.network_filters == 1
+
+    report = filter_set.add_filters(
+        ["||ads.example.net^", "example.com##.ad"],
+        adblock.FilterFormat.STANDARD,
+        rule_types=adblock.RuleTypes.COSMETIC_ONLY,
+    )
+    assert report.network_filters == 0
+    assert report.cosmetic_filters == 1
+
+
+def test_strings_accepted():
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filters(
+        ["||ads.example.net^", "example.com##.ad"], "standard", False, "networkonly"
+    )
+    assert report.network_filters == 1
+    assert report.cosmetic_filters == 0
+
+
+def test_invalid_format():
+    with pytest.raises(adblock.InvalidOptionError) as exc_info:
+        adblock.FilterSet().add_filter_list("", format="adblock")
+    message = str(exc_info.value)
+    assert "'adblock'" in message
+    assert "'standard'" in message
+    assert "'adguard_dns'" in message
+
+
+def test_invalid_rule_types():
+    with pytest.raises(adblock.InvalidOptionError) as exc_info:
+        adblock.FilterSet().add_filters([], rule_types="network")
+    assert str(exc_info.value) == (
+        "Invalid rule_types 'network', expected one of "
+        "'all', 'networkonly', 'cosmeticonly'"
+    )
+    assert issubclass(adblock.InvalidOptionError, adblock.AdblockException)
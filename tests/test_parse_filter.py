@@ -0,0 +1,108 @@
+import adblock
+import pytest
+
+
+def test_network_filter():
+    f = adblock.parse_filter(
+        "@@||ads.example.com/x^$script,xhr,3p,domain=a.com|~b.a.com,important"
+    )
+    assert isinstance(f, adblock.NetworkFilter)
+    assert f.exception
+    assert f.pattern == "ads.example.com/x^"
+    assert f.hostname_anchor
+    assert not f.left_anchor
+    assert not f.right_anchor
+    assert f.domains == {"a.com"}
+    assert f.excluded_domains == {"b.a.com"}
+    assert f.request_types == {"script", "xmlhttprequest"}
+    assert f.third_party
+    assert not f.first_party
+    assert f.important
+    assert not f.is_regex
+    assert repr(f) == "NetworkFilter('{}')".format(f.text)
+
+
+def test_network_filter_defaults():
+    f = adblock.parse_filter("|https://example.com/ad.js|")
+    assert f.pattern == "https://example.com/ad.js"
+    assert f.left_anchor
+    assert f.right_anchor
+    assert f.domains == set()
+    assert "document" not in f.request_types
+    assert {"image", "script", "subdocument"} <= f.request_types
+    assert f.first_party
+    assert f.third_party
+    assert not f.exception
+    assert f.redirect is None
+    assert f.tag is None
+    assert f.csp is None
+
+
+def test_network_filter_options():
+    f = adblock.parse_filter("ads$redirect=noop.js,tag=t,~image,match-case")
+    assert f.redirect == "noop.js"
+    assert f.tag == "t"
+    assert f.match_case
+    assert "image" not in f.request_types
+    assert adblock.parse_filter("/ba[rz]/$csp=script-src 'none'").csp == (
+        "script-src 'none'"
+    )
+    assert adblock.parse_filter("/ba[rz]/").is_regex
+    assert adblock.parse_filter("||a.com^$badfilter").badfilter
+    assert adblock.parse_filter("@@||a.com^$generichide").generichide
+
+
+def test_cosmetic_filter():
+    f = adblock.parse_filter("a.com,~b.a.com,example.*##div.ad")
+    assert isinstance(f, adblock.CosmeticFilter)
+    assert not f.exception
+    assert f.hostnames == {"a.com", "example.*"}
+    assert f.excluded_hostnames == {"b.a.com"}
+    assert f.selector == "div.ad"
+    assert f.style is None
+    assert f.scriptlet is None
+    assert f.scriptlet_args == []
+    assert repr(f) == "CosmeticFilter('a.com,~b.a.com,example.*##div.ad')"
+
+    f = adblock.parse_filter("a.com#@#p:style(color: red)")
+    assert f.exception
+    assert f.selector == "p"
+    assert f.style == "color: red"
+
+
+def test_scriptlet():
+    f = adblock.parse_filter(r'a.com##+js(set-constant, a\, b, "c")')
+    assert f.selector is None
+    assert f.scriptlet == "set-constant"
+    assert f.scriptlet_args == ["a, b", "c"]
+
+
+@pytest.mark.parametrize(
+    "text, filter_type, kind, message",
+    [
+        (
+            "x$foo",
+            "network",
+            "unrecognised_option",
+            "Invalid network filter 'x$foo': unrecognised_option",
+        ),
+        ("##a:has-text(x)", "cosmetic", "unsupported_syntax", "unsupported_syntax"),
+        ("! comment", None, None, "is not a filter rule"),
+        ("", None, None, "is not a filter rule"),
+    ],
+)
+def test_parse_error(text, filter_type, kind, message):
+    with pytest.raises(adblock.FilterParseError) as exc:
+        adblock.parse_filter(text)
+    assert message in str(exc.value)
+    assert exc.value.text == text
+    assert exc.value.filter_type == filter_type
+    assert exc.value.kind == kind
+    assert isinstance(exc.value, adblock.AdblockException)
+
+
+def test_include_redirect_urls():
+    text = "||a.com^$redirect-url=https://example.com/"
+    with pytest.raises(adblock.FilterParseError):
+        adblock.parse_filter(text)
+    adblock.parse_filter(text, include_redirect_urls=True)
@@ -0,0 +1,56 @@
+import adblock
+
+FILTER_LIST = """[Adblock Plus 2.0]
+! Title: Test list
+
+||ads.example.com^
+||tracker.example.com^$unknown-option
+/banner[0-9/$image
+example.com##.ad
+example.com##+js(nowebrtc)
+#@#.sponsored
+||example.org^$redirect=
+"""
+
+
+def test_add_filter_list_report():
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filter_list(FILTER_LIST)
+    assert isinstance(report, adblock.ParseReport)
+    assert report.network_filters == 2
+    assert report.cosmetic_filters == 2
+    assert report.ignored == 3
+    assert [(r.line_number, r.filter_type, r.kind) for r in report.rejected] == [
+        (5, "network", "unrecognised_option"),
+        (9, "cosmetic", "generic_unhide"),
+        (10, "network", "empty_redirection"),
+    ]
+    assert report.rejected[0].text == "||tracker.example.com^$unknown-option"
+
+
+def test_add_filters_report():
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filters(["||a.com^", "", "||b.com^$csp=x,image"])
+    assert report.network_filters == 1
+    assert report.ignored == 1
+    (rejected,) = report.rejected
+    assert rejected.line_number == 3
+    assert rejected.kind == "csp_with_content_type"
+
+
+def test_report_rule_types():
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filter_list(FILTER_LIST, rule_types="networkonly")
+    assert report.cosmetic_filters == 0
+    assert report.network_filters == 2
+    assert all(r.filter_type == "network" for r in report.rejected)
+
+
+def test_report_hosts():
+    filter_set = adblock.FilterSet()
+    report = filter_set.add_filter_list(
+        "# comment\n127.0.0.1 localhost\n0.0.0.0 ads.example.com\n", format="hosts"
+    )
+    assert report.network_filters == 1
+    assert report.ignored == 2
+    assert report.rejected == []
@@ -0,0 +1,186 @@
+import adblock
+import pathlib
+import pytest
+
+FILTER_LIST = """||always.example^
+!#if env_firefox
+||firefox.example^
+!#if !env_mobile
+||firefox-desktop.example^
+!#endif
+!#else
+||not-firefox.example^
+!#endif
+!#if (env_chromium || env_edge) && !env_mobile
+||chromium-desktop.example^
+!#endif
+"""
+
+
+def blocked(filter_set, host):
+    engine = adblock.Engine(filter_set)
+    url = "https://{}/ad.js".format(host)
+    return engine.check_network_urls(url, "https://news.com/", "script").matched
+
+
+def hosts(filter_set):
+    return [line[2:-1] for line in filter_set]
+
+
+def test_directives_are_comments_by_default():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(FILTER_LIST)
+    assert len(filter_set) == 5
+
+
+def test_conditions():
+    filter_set = adblock.FilterSet(debug=True)
+    report = filter_set.add_filter_list(FILTER_LIST, env={"env_firefox"})
+    assert hosts(filter_set) == [
+        "always.example",
+        "firefox.example",
+        "firefox-desktop.example",
+    ]
+    assert report.network_filters == 3
+    assert report.ignored == 9
+
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(FILTER_LIST, env=["env_firefox", "env_mobile"])
+    assert hosts(filter_set) == ["always.example", "firefox.example"]
+
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(FILTER_LIST, env=["env_edge"])
+    assert hosts(filter_set) == [
+        "always.example",
+        "not-firefox.example",
+        "chromium-desktop.example",
+    ]
+
+
+def test_malformed_condition_is_false():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(
+        "!#if (env_firefox\n||a.example^\n!#endif\n||b.example^", env=["env_firefox"]
+    )
+    assert hosts(filter_set) == ["b.example"]
+
+
+def test_env_must_not_be_a_string():
+    with pytest.raises(TypeError):
+        adblock.FilterSet().add_filter_list(FILTER_LIST, env="env_firefox")
+
+
+def test_include_callable():
+    lists = {
+        "sub.txt": "||sub.example^\n!#include nested.txt\n||bad^$nope",
+        "nested.txt": "||nested.example^",
+    }
+    filter_set = adblock.FilterSet(debug=True)
+    report = filter_set.add_filter_list(
+        "||main.example^\n!#include sub.txt\n!#include missing.txt",
+        include_resolver=lists.get,
+    )
+    assert hosts(filter_set) == ["main.example", "sub.example", "nested.example"]
+    assert report.unresolved_includes == ["missing.txt"]
+    assert report.include_cycles == []
+    (rejected,) = report.rejected
+    assert (rejected.include, rejected.line_number) == ("sub.txt", 3)
+
+
+def test_include_inside_condition():
+    filter_set = adblock.FilterSet(debug=True)
+    report = filter_set.add_filter_list(
+        "!#if env_safari\n!#include safari.txt\n!#endif",
+        env=[],
+        include_resolver=lambda name: "||safari.example^",
+    )
+    assert len(filter_set) == 0
+    assert report.unresolved_includes == []
+
+
+def test_include_without_resolver():
+    report = adblock.FilterSet().add_filter_list("!#include a.txt", env=[])
+    assert report.unresolved_includes == ["a.txt"]
+
+
+def test_include_cycle():
+    lists = {
+        "a.txt": "||a.example^\n!#include b.txt",
+        "b.txt": "||b.example^\n!#include a.txt",
+    }
+    filter_set = adblock.FilterSet(debug=True)
+    report = filter_set.add_filter_list("!#include a.txt", include_resolver=lists.get)
+    assert hosts(filter_set) == ["a.example", "b.example"]
+    assert report.include_cycles == ["a.txt"]
+
+
+def test_include_directory(tmpdir):
+    directory = pathlib.Path(str(tmpdir))
+    (directory / "lists").mkdir()
+    (directory / "lists" / "sub.txt").write_text("||sub.example^\n")
+    (directory / "secret.txt").write_text("||secret.example^\n")
+
+    filter_set = adblock.FilterSet(debug=True)
+    report = filter_set.add_filter_list(
+        "!#include sub.txt\n!#include ../secret.txt",
+        include_resolver=directory / "lists",
+    )
+    assert hosts(filter_set) == ["sub.example"]
+    assert report.unresolved_includes == ["../secret.txt"]
+    assert blocked(filter_set, "sub.example")
+
+
+def test_nested_include_directory(tmpdir):
+    directory = pathlib.Path(str(tmpdir))
+    (directory / "sub" / "deeper").mkdir(parents=True)
+    (directory / "sub" / "a.txt").write_text(
+        "||a.example^\n!#include deeper/b.txt\n!#include ../top.txt\n"
+    )
+    (directory / "sub" / "deeper" / "b.txt").write_text(
+        "||b.example^\n!#include c.txt\n!#include ../../../outside.txt\n"
+    )
+    (directory / "sub" / "deeper" / "c.txt").write_text("||c.example^\n")
+    (directory / "c.txt").write_text("||wrong.example^\n")
+    (directory / "top.txt").write_text("||top.example^\n")
+
+    filter_set = adblock.FilterSet(debug=True)
+    report = filter_set.add_filter_list(
+        "!#include sub/a.txt", include_resolver=directory
+    )
+    assert sorted(hosts(filter_set)) == [
+        "a.example",
+        "b.example",
+        "c.example",
+        "top.example",
+    ]
+    assert report.unresolved_includes == ["../outside.txt"]
+
+
+def test_nested_include_callable():
+    lists = {
+        "sub/a.txt": "||a.example^\n!#include b.txt",
+        "sub/b.txt": "||b.example^\n!#include a.txt",
+    }
+    requested = []
+
+    def resolver(name):
+        requested.append(name)
+        return lists.get(name)
+
+    filter_set = adblock.FilterSet(debug=True)
+    report = filter_set.add_filter_list(
+        "!#include sub/a.txt", include_resolver=resolver
+    )
+    assert requested == ["sub/a.txt", "sub/b.txt"]
+    assert report.include_cycles == ["sub/a.txt"]
+    assert sorted(hosts(filter_set)) == ["a.example", "b.example"]
+
+
+def test_include_resolver_errors_propagate():
+    def resolver(name):
+        raise ValueError(name)
+
+    with pytest.raises(ValueError):
+        adblock.FilterSet().add_filter_list(
+            "!#include a.txt", include_resolver=resolver
+        )
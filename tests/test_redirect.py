@@ -76,3 +76,85 @@ def test_redirect_with_custom_resource():
     assert not result.exception
     assert not result.important
     assert result.redirect == "data:application/javascript;base64,YWxlcnQoMSk="
+    assert result.redirect_resource_name == "test"
+    assert result.redirect_mime_type == "application/javascript"
+    assert result.redirect_bytes == b"alert(1)"
+    assert result.redirect_data_url == result.redirect
+
+
+def test_redirect_resource_alias():
+    filters = adblock.FilterSet()
+    filters.add_filter_list("||ads.example.com^$image,redirect=1x1.gif\n")
+
+    engine = adblock.Engine(filter_set=filters)
+    engine.add_resource(
+        name="1x1-transparent.gif",
+        content_type="image/gif",
+        content="R0lGODlhAQABAIAAAAAAAP///yH5BAEAAAAALAAAAAABAAEAAAIBRAA7",
+        aliases=["1x1.gif"],
+    )
+
+    result = engine.check_network_urls(
+        "https://ads.example.com/pixel.gif", "https://news.com/", "image"
+    )
+    assert result.redirect_type == "resource"
+    assert result.redirect_resource_name == "1x1-transparent.gif"
+    assert result.redirect_mime_type == "image/gif"
+    assert result.redirect_bytes.startswith(b"GIF89a")
+
+    deserialized = adblock.Engine(adblock.FilterSet())
+    deserialized.deserialize(engine.serialize())
+    result = deserialized.check_network_urls(
+        "https://ads.example.com/pixel.gif", "https://news.com/", "image"
+    )
+    assert result.redirect_resource_name == "1x1-transparent.gif"
+    assert result.redirect_bytes.startswith(b"GIF89a")
+
+
+def test_redirect_resources_with_same_content():
+    filters = adblock.FilterSet()
+    filters.add_filters(
+        [
+            "||ads.example.com^$script,redirect=noop.js",
+            "||ads.example.com^$xhr,redirect=noop.txt",
+        ]
+    )
+    engine = adblock.Engine(filter_set=filters)
+    engine.add_resource(name="noop.js", content_type="text/plain", content="")
+    engine.add_resource(name="noop.txt", content_type="text/plain", content="")
+
+    def name(request_type):
+        result = engine.check_network_urls(
+            "https://ads.example.com/a", "https://news.com/", request_type
+        )
+        return result.redirect_resource_name
+
+    assert name("script") == "noop.js"
+    assert name("xmlhttprequest") == "noop.txt"
+
+    # The filters telling them apart aren't serialized.
+    serialized = engine.serialize()
+    engine = adblock.Engine(adblock.FilterSet())
+    engine.deserialize(serialized)
+    assert name("script") is None
+
+
+def test_serialize_keeps_upstream_format():
+    filters = adblock.FilterSet()
+    filters.add_filters(["||ads.example.com^$script,redirect=noop.js"])
+    serialized = adblock.Engine(filter_set=filters).serialize()
+    assert not serialized.startswith(b"\xffpython-adblock-extras")
+
+
+def test_redirect_url_has_no_resource_fields():
+    filter_set = adblock.FilterSet()
+    filter_set.add_filter_list(
+        "||foo.com$important,redirect-url=http://xyz.com", include_redirect_urls=True
+    )
+    engine = adblock.Engine(filter_set=filter_set)
+
+    res = engine.check_network_urls("https://foo.com", "https://foo.com", "script")
+    assert res.redirect_resource_name is None
+    assert res.redirect_mime_type is None
+    assert res.redirect_bytes is None
+    assert res.redirect_data_url is None
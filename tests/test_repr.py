@@ -26,12 +26,17 @@ def test_has_nondefault_repr():
     )
     assert_acceptable_repr(result)
     assert repr(result) == (
-        "BlockerResult(matched={}, important={}, redirect={}, exception={}, filter={}, error={})".format(
+        "BlockerResult(matched={}, important={}, redirect={}, redirect_type={}, redirect_resource_name={}, exception={}, filter={}, error={}, filter_source={}, exception_source={})".format(
             repr(result.matched),
             repr(result.important),
             repr(result.redirect),
+            repr(result.redirect_type),
+            repr(result.redirect_resource_name),
             repr(result.exception),
             repr(result.filter),
             repr(result.error),
+            repr(result.filter_source),
+            repr(result.exception_source),
         )
     )
+    assert eval(repr(result), {"BlockerResult": adblock.BlockerResult}) == result
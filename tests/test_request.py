@@ -0,0 +1,68 @@
+import adblock
+import pytest
+
+FILTER_LIST = """
+||ads.example.com^$third-party
+||tracker.net^$script
+"""
+
+
+def test_request_properties():
+    request = adblock.Request(
+        "https://ads.example.com/banner.png", "https://www.news.co.uk/article", "image"
+    )
+    assert request.url == "https://ads.example.com/banner.png"
+    assert request.hostname == "ads.example.com"
+    assert request.domain == "example.com"
+    assert request.source_hostname == "www.news.co.uk"
+    assert request.source_domain == "news.co.uk"
+    assert request.request_type == "image"
+    assert request.third_party is True
+
+    first_party = adblock.Request(
+        "https://cdn.example.com/app.js", "https://example.com/", "script"
+    )
+    assert first_party.third_party is False
+
+
+def test_request_type_classification():
+    def classify(url, request_type):
+        return adblock.Request(url, "", request_type).request_type
+
+    assert classify("https://a.com/", "xhr") == "xmlhttprequest"
+    assert classify("https://a.com/", "main_frame") == "document"
+    assert classify("wss://a.com/", "script") == "websocket"
+    assert classify("https://a.com/", "unknown") == "other"
+
+
+def test_request_without_source():
+    request = adblock.Request("https://a.com/", "", "image")
+    assert request.source_hostname == ""
+    assert request.third_party is None
+
+
+def test_invalid_request():
+    with pytest.raises(adblock.InvalidRequestError):
+        adblock.Request("not a url", "https://example.com/", "image")
+
+
+def test_check_request_matches_check_network_urls():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(FILTER_LIST)
+    engines = [
+        adblock.Engine(filter_set, optimize=True),
+        adblock.Engine(filter_set, optimize=False),
+    ]
+    cases = [
+        ("https://ads.example.com/banner.png", "https://news.com/", "image"),
+        ("https://ads.example.com/banner.png", "https://example.com/", "image"),
+        ("https://tracker.net/t.js", "https://news.com/", "script"),
+        ("https://tracker.net/t.png", "https://news.com/", "image"),
+    ]
+    for args in cases:
+        request = adblock.Request(*args)
+        for engine in engines:
+            result = engine.check(request)
+            expected = engine.check_network_urls(*args)
+            assert result.matched == expected.matched
+            assert result.filter == expected.filter
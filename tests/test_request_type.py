@@ -0,0 +1,91 @@
+import adblock
+import pytest
+
+FILTER_LIST = """
+||example.com^$script
+||example.net^$xmlhttprequest
+"""
+
+
+def engine():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filter_list(FILTER_LIST)
+    return adblock.Engine(filter_set)
+
+
+def test_enum_values():
+    assert adblock.RequestType.SCRIPT.value == "script"
+    assert adblock.RequestType.XMLHTTPREQUEST.value == "xmlhttprequest"
+    assert adblock.RequestType.CSP_REPORT.value == "csp_report"
+    assert adblock.RequestType.SCRIPT == adblock.RequestType.SCRIPT
+    assert adblock.RequestType.SCRIPT != adblock.RequestType.IMAGE
+
+
+def test_enum_and_string_agree():
+    e = engine()
+    url, source_url = "https://example.com/a.js", "https://news.com/"
+    assert e.check_network_urls(url, source_url, "script").matched
+    assert e.check_network_urls(url, source_url, adblock.RequestType.SCRIPT).matched
+    assert not e.check_network_urls(url, source_url, adblock.RequestType.IMAGE).matched
+
+    url = "https://example.net/api"
+    assert e.check_network_urls(url, source_url, "xhr").matched
+    assert e.check_network_urls(
+        url, source_url, adblock.RequestType.XMLHTTPREQUEST
+    ).matched
+    assert e.check_network_urls_with_hostnames(
+        url, "example.net", "news.com", adblock.RequestType.XMLHTTPREQUEST, None
+    ).matched
+    assert e.check_network_urls_with_hostnames_subset(
+        url,
+        "example.net",
+        "news.com",
+        adblock.RequestType.XMLHTTPREQUEST,
+        None,
+        False,
+        False,
+    ).matched
+
+    results = e.check_network_urls_batch(
+        [
+            (url, source_url, adblock.RequestType.XMLHTTPREQUEST),
+            (url, source_url, "xhr"),
+        ]
+    )
+    assert [r.matched for r in results] == [True, True]
+
+    request = adblock.Request(url, source_url, adblock.RequestType.XMLHTTPREQUEST)
+    assert request.request_type == "xmlhttprequest"
+    assert e.check(request).matched
+
+
+def test_unknown_string_falls_back_by_default():
+    e = engine()
+    url, source_url = "https://example.com/a.js", "https://news.com/"
+    result = e.check_network_urls(url, source_url, "scirpt")
+    assert not result.matched
+    assert adblock.Request("https://a.com/", "", "scirpt").request_type == "other"
+
+
+def test_strict_mode_rejects_unknown_strings():
+    e = engine()
+    url, source_url = "https://example.com/a.js", "https://news.com/"
+    with pytest.raises(adblock.InvalidRequestTypeError, match="scirpt"):
+        e.check_network_urls(url, source_url, "scirpt", strict=True)
+    with pytest.raises(adblock.InvalidRequestTypeError):
+        e.check_network_urls_with_hostnames(
+            url, "example.com", "news.com", "scirpt", None, strict=True
+        )
+    with pytest.raises(adblock.InvalidRequestTypeError):
+        e.check_network_urls_with_hostnames_subset(
+            url, "example.com", "news.com", "scirpt", None, False, False, strict=True
+        )
+    with pytest.raises(adblock.InvalidRequestTypeError):
+        e.check_network_urls_batch([(url, source_url, "scirpt")], strict=True)
+    with pytest.raises(adblock.InvalidRequestTypeError):
+        adblock.Request(url, source_url, "scirpt", strict=True)
+
+    # Aliases understood by the request parser are accepted.
+    for alias in ("xhr", "main_frame", "sub_frame", "imageset", "object_subrequest"):
+        e.check_network_urls(url, source_url, alias, strict=True)
+    assert e.check_network_urls(url, source_url, "script", strict=True).matched
@@ -0,0 +1,177 @@
+import adblock
+import base64
+import pytest
+
+# An engine serialized by a version without sources, from the filters
+# "||ads.example.com^", "@@||ads.example.com/ok/*" and "example.org##.banner".
+OLD_SERIALIZED_ENGINE = base64.b64decode(
+    "0dk6rwDcABGRgJGBzybQ91ekoGyWkZ2RzgBrH/+BAaQvb2svwMDAr2Fkcy5leGFtcGxlLmNvbcDA"
+    "wLhAQHx8YWRzLmV4YW1wbGUuY29tL29rLyrP4EhSUYsLF1DAwJGAkYCRgJGBz1B2eFvqOkBVkZ2R"
+    "ziAzH/+BAMDAwMCvYWRzLmV4YW1wbGUuY29twMDAsnx8YWRzLmV4YW1wbGUuY29tXs9xa48YG5n6"
+    "TMDAkYCQw5GAkJCAgJGBz8GnCZzMmpwnkYEApy5iYW5uZXKQkYA="
+)
+
+
+def engine_with_sources(debug=True, optimize=True):
+    filter_set = adblock.FilterSet(debug=debug)
+    filter_set.add_filter_list(
+        "||ads.example.com^\nexample.org##.banner\n",
+        source="easylist",
+    )
+    filter_set.add_filters(
+        ["@@||ads.example.com/ok/*", "example.org##.popup"], source="allowlist"
+    )
+    filter_set.add_filters(["||tracker.example.com^", "example.org##.cookie"])
+    return adblock.Engine(filter_set, optimize=optimize)
+
+
+def test_filter_source():
+    engine = engine_with_sources()
+    result = engine.check_network_urls(
+        "https://ads.example.com/a.png", "https://news.com/", "image"
+    )
+    assert result.matched
+    assert result.filter == "||ads.example.com^"
+    assert result.filter_source == "easylist"
+    assert result.exception_source is None
+
+
+def test_filter_source_without_debug():
+    engine = engine_with_sources(debug=False)
+    result = engine.check_network_urls(
+        "https://ads.example.com/a.png", "https://news.com/", "image"
+    )
+    assert result.filter == "NetworkFilter"
+    assert result.filter_source is None
+    resources = engine.url_cosmetic_resources("https://example.org/")
+    assert resources.selector_sources[".banner"] == ["easylist"]
+
+
+def test_exception_source():
+    engine = engine_with_sources()
+    result = engine.check_network_urls(
+        "https://ads.example.com/ok/a.png", "https://news.com/", "image"
+    )
+    assert not result.matched
+    assert result.filter_source == "easylist"
+    assert result.exception == "@@||ads.example.com/ok/*"
+    assert result.exception_source == "allowlist"
+
+
+def test_no_source():
+    engine = engine_with_sources()
+    result = engine.check_network_urls(
+        "https://tracker.example.com/a.js", "https://news.com/", "script"
+    )
+    assert result.filter == "||tracker.example.com^"
+    assert result.filter_source is None
+
+
+def test_merged_filter_sources():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filters(["/static/ad-"], source="a")
+    filter_set.add_filters(["/static/ad."], source="b")
+    engine = adblock.Engine(filter_set, optimize=True)
+    result = engine.check_network_urls(
+        "https://example.com/static/ad-1.png", "https://news.com/", "image"
+    )
+    # The optimizer merges both filters, so either of them may have matched.
+    assert set(result.filter.split(" <+> ")) == {"/static/ad-", "/static/ad."}
+    assert set(result.filter_source.split(" <+> ")) == {"a", "b"}
+
+
+@pytest.mark.parametrize("optimize", [True, False])
+def test_duplicate_filter_sources(optimize):
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filters(["||ads.example.com^"], source="a")
+    filter_set.add_filters(["||ads.example.com^", "||ads.example.net^"], source="b")
+    filter_set.add_filters(["||ads.example.com^"], source="a")
+    engine = adblock.Engine(filter_set, optimize=optimize)
+
+    def source(url):
+        result = engine.check_network_urls(url, "https://news.com/", "image")
+        return result.filter_source
+
+    assert source("https://ads.example.com/a.png") == "a <+> b"
+    assert source("https://ads.example.net/a.png") == "b"
+
+
+def test_selector_sources():
+    engine = engine_with_sources()
+    resources = engine.url_cosmetic_resources("https://example.org/")
+    assert resources.selector_sources == {
+        ".banner": ["easylist"],
+        ".popup": ["allowlist"],
+    }
+
+
+def test_sources_survive_serialization():
+    engine = engine_with_sources()
+    deserialized = adblock.Engine(adblock.FilterSet())
+    deserialized.deserialize(engine.serialize())
+
+    result = deserialized.check_network_urls(
+        "https://ads.example.com/ok/a.png", "https://news.com/", "image"
+    )
+    assert result.filter_source == "easylist"
+    assert result.exception_source == "allowlist"
+    resources = deserialized.url_cosmetic_resources("https://example.org/")
+    assert resources.selector_sources[".popup"] == ["allowlist"]
+
+
+def test_sources_survive_filter_set_operations():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filters(["||ads.example.com^"], source="a")
+    other = adblock.FilterSet(debug=True)
+    other.add_filters(["||ads.example.com^", "||ads.example.net^"], source="b")
+
+    merged = adblock.FilterSet(debug=True)
+    merged.deserialize((filter_set | other).serialize())
+    engine = adblock.Engine(merged)
+
+    def source(url):
+        result = engine.check_network_urls(url, "https://news.com/", "image")
+        return result.filter_source
+
+    # Duplicates keep the source of the first copy.
+    assert source("https://ads.example.com/a.png") == "a"
+    assert source("https://ads.example.net/a.png") == "b"
+
+
+def test_deserialize_without_sources():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filters(["||ads.example.com^", "@@||ads.example.com/ok/*"])
+    serialized = adblock.Engine(filter_set).serialize()
+
+    for serialized in (serialized, OLD_SERIALIZED_ENGINE):
+        engine = engine_with_sources()
+        engine.deserialize(serialized)
+        result = engine.check_network_urls(
+            "https://ads.example.com/ok/a.png", "https://news.com/", "image"
+        )
+        assert result.filter == "||ads.example.com^"
+        assert result.exception == "@@||ads.example.com/ok/*"
+        assert result.filter_source is None
+        resources = engine.url_cosmetic_resources("https://example.org/")
+        assert resources.selector_sources == {}
+    resources = engine.url_cosmetic_resources("https://example.org/")
+    assert ".banner" in resources.hide_selectors
+
+
+def test_raw_line_unchanged():
+    filter_set = adblock.FilterSet(debug=True)
+    filter_set.add_filters(["||ads.example.com^"], source="easylist")
+    engine = adblock.Engine(filter_set, keep_filters=True)
+    explanation = engine.explain(
+        "https://ads.example.com/", "https://news.com/", "image"
+    )
+    assert [m.filter for m in explanation.matches] == ["||ads.example.com^"]
+
+
+def test_deserialize_unknown_version():
+    serialized = engine_with_sources().serialize()
+    magic = b"\xffpython-adblock-extras"
+    assert serialized.startswith(magic)
+    engine = adblock.Engine(adblock.FilterSet())
+    with pytest.raises(adblock.DeserializationError):
+        engine.deserialize(magic + b"\x02" + serialized[len(magic) + 1 :])